@@ -0,0 +1,33 @@
+use std::io;
+use std::iter;
+
+mod thinking;
+mod uci;
+mod xboard;
+
+use uci::Uci;
+
+// The engine on its own, for chess GUIs and tournament scripts. It talks UCI or XBoard (CECP)
+// over stdin and stdout and doesn't need bevy:
+//     cargo run --release --no-default-features --bin engine
+
+fn main() {
+    // GUIs open with "uci" or "xboard", which settles which protocol it is
+    let first = match io::stdin().lines().map_while(Result::ok).find(|line| !line.trim().is_empty()) {
+        Some(first) => first,
+        None => return,
+    };
+    if first.trim() == "xboard" {
+        xboard::run();
+        return;
+    }
+
+    let mut uci = Uci::new();
+    for line in iter::once(first).chain(io::stdin().lines().map_while(Result::ok)) {
+        if !uci.handle(&line) {
+            return;
+        }
+    }
+    // The GUI went away without saying quit
+    uci.handle("quit");
+}
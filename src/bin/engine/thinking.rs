@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use rust_chess_engine::core::{GameState, Move, Search, SearchLimits, SearchResult, Undo};
+
+// The game as the GUI has told us about it: where it stands now and the hashes of every
+// position before, so the search can see repetitions
+pub struct Game {
+    pub state: GameState,
+    pub history: Vec<u64>,
+    // What's needed to take each move back again
+    moves: Vec<(Move, Undo)>,
+}
+
+impl Game {
+    pub fn new(state: GameState) -> Self {
+        Game { state, history: Vec::new(), moves: Vec::new() }
+    }
+
+    // `mv` has to be legal
+    pub fn play(&mut self, mv: Move) {
+        self.history.push(self.state.hash);
+        let undo = self.state.make_move(mv);
+        self.moves.push((mv, undo));
+    }
+
+    // Takes back the last move, returning false if there wasn't one
+    pub fn undo(&mut self) -> bool {
+        match self.moves.pop() {
+            Some((mv, undo)) => {
+                self.state.unmake_move(mv, undo);
+                self.history.pop();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Runs searches on their own thread so the protocol can keep reading commands, stop included
+pub struct Thinker {
+    search: Arc<Mutex<Search>>,
+    stop: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Thinker {
+    pub fn new() -> Self {
+        Thinker { search: Arc::new(Mutex::new(Search::default())), stop: None, thread: None }
+    }
+
+    // The search itself, for changing its settings. Don't hold on to it while thinking.
+    pub fn search(&self) -> MutexGuard<'_, Search> {
+        self.search.lock().unwrap()
+    }
+
+    // Starts thinking about `game`. `report` gets every finished iteration and `done` the final
+    // result, both on the search thread. `limits.stop` gets filled in if it's empty.
+    pub fn start(
+        &mut self,
+        game: &Game,
+        mut limits: SearchLimits,
+        mut report: impl FnMut(&SearchResult) + Send + 'static,
+        done: impl FnOnce(SearchResult) + Send + 'static,
+    ) {
+        self.stop();
+        let stop = limits.stop.get_or_insert_with(|| Arc::new(AtomicBool::new(false))).clone();
+        self.stop = Some(stop);
+
+        let search = self.search.clone();
+        let state = game.state;
+        let history = game.history.clone();
+        self.thread = Some(thread::spawn(move || {
+            let result = search.lock().unwrap().search(&state, &history, &limits, |iteration| report(iteration));
+            done(result);
+        }));
+    }
+
+    // Tells the search to wrap up and waits for it
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        self.wait();
+    }
+
+    // Stops the current search once `time` has gone by, if it hasn't stopped by then
+    pub fn stop_after(&self, time: Duration) {
+        if let Some(stop) = self.stop.clone() {
+            thread::spawn(move || {
+                thread::sleep(time);
+                stop.store(true, Ordering::Relaxed);
+            });
+        }
+    }
+
+    // Waits for the search to finish on its own
+    pub fn wait(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+// How long to spend on a move with `remaining` on the clock, `increment` added after each move
+// and `moves_to_go` until the next time control, if there is one
+pub fn time_budget(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    // Without a time control coming up, plan for the game to go on about another 30 moves
+    let moves = moves_to_go.unwrap_or(30).max(1);
+    let budget = remaining / moves + increment * 3 / 4;
+    // Always leave a little on the clock for getting the move back to the GUI
+    let margin = Duration::from_millis(50);
+    budget.min(remaining.saturating_sub(margin)).max(Duration::from_millis(10))
+}
@@ -0,0 +1,281 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use rust_chess_engine::core::{parse_fen, Move, Score, SearchLimits, SearchResult, STARTING_BOARD_FEN};
+
+use crate::thinking::*;
+
+// The Universal Chess Interface, which most GUIs and tournament managers speak.
+// See https://www.wbec-ridderkerk.nl/html/UCIProtocol.html
+
+const DEFAULT_HASH: usize = 16;
+const MAX_SKILL: u32 = 20;
+
+struct Options {
+    hash: usize,
+    multi_pv: usize,
+    skill_level: u32,
+}
+
+pub struct Uci {
+    thinker: Thinker,
+    game: Game,
+    options: Options,
+    // Set while the GUI has us thinking on its time, waiting to hear whether it guessed the move right
+    pondering: Arc<AtomicBool>,
+    // How long to go on thinking once a ponder search turns into a real one
+    ponder_budget: Option<Duration>,
+    random: u64,
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |since| since.as_nanos() as u64);
+        Uci {
+            thinker: Thinker::new(),
+            game: Game::new(parse_fen(STARTING_BOARD_FEN).unwrap()),
+            options: Options { hash: DEFAULT_HASH, multi_pv: 1, skill_level: MAX_SKILL },
+            pondering: Arc::new(AtomicBool::new(false)),
+            ponder_budget: None,
+            // Xorshift can't start from zero
+            random: seed | 1,
+        }
+    }
+
+    // Handles one line from the GUI. Returns false once it's time to quit.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return true,
+        };
+
+        match command {
+            "uci" => {
+                println!("id name Rusty Chess {}", env!("CARGO_PKG_VERSION"));
+                println!("id author {}", env!("CARGO_PKG_AUTHORS"));
+                println!("option name Hash type spin default {} min 1 max 4096", DEFAULT_HASH);
+                // The search only runs on one thread, so there's nothing to set
+                println!("option name Threads type spin default 1 min 1 max 1");
+                println!("option name MultiPV type spin default 1 min 1 max 256");
+                println!("option name Skill Level type spin default {} min 0 max {}", MAX_SKILL, MAX_SKILL);
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.thinker.stop();
+                self.thinker.search().clear();
+                self.game = Game::new(parse_fen(STARTING_BOARD_FEN).unwrap());
+            }
+            "setoption" => self.set_option(args),
+            "position" => self.position(args),
+            "go" => self.go(args),
+            "stop" => {
+                self.pondering.store(false, Ordering::Relaxed);
+                self.thinker.stop();
+            }
+            "ponderhit" => {
+                self.pondering.store(false, Ordering::Relaxed);
+                if let Some(budget) = self.ponder_budget.take() {
+                    self.thinker.stop_after(budget);
+                }
+            }
+            "quit" => {
+                self.thinker.stop();
+                return false;
+            }
+            // Nothing to do for these
+            "debug" | "register" => {}
+            _ => println!("info string unknown command {}", command),
+        }
+        true
+    }
+
+    // setoption name <name> [value <value>], where both can have spaces in them
+    fn set_option(&mut self, args: &[&str]) {
+        let value_at = args.iter().position(|word| *word == "value").unwrap_or(args.len());
+        let name = args.get(1..value_at).unwrap_or(&[]).join(" ").to_lowercase();
+        let value = args.get(value_at + 1..).unwrap_or(&[]).join(" ");
+
+        match (name.as_str(), value.parse::<usize>()) {
+            ("hash", Ok(megabytes)) => {
+                self.thinker.stop();
+                self.options.hash = megabytes.max(1);
+                self.thinker.search().set_hash_size(self.options.hash);
+            }
+            ("threads", Ok(_)) => {}
+            ("multipv", Ok(lines)) => self.options.multi_pv = lines.max(1),
+            ("skill level", Ok(level)) => self.options.skill_level = (level as u32).min(MAX_SKILL),
+            _ => println!("info string can't set {} to '{}'", name, value),
+        }
+    }
+
+    // position startpos|fen <fen> [moves <move>...]
+    fn position(&mut self, args: &[&str]) {
+        // Whatever was being thought about, infinite and ponder searches included, is done with
+        self.pondering.store(false, Ordering::Relaxed);
+        self.ponder_budget = None;
+        self.thinker.stop();
+
+        let moves_at = args.iter().position(|word| *word == "moves").unwrap_or(args.len());
+        let start = match args.first() {
+            Some(&"startpos") => parse_fen(STARTING_BOARD_FEN),
+            Some(&"fen") => parse_fen(&args[1..moves_at].join(" ")),
+            _ => {
+                println!("info string position needs startpos or fen");
+                return;
+            }
+        };
+        let mut game = match start {
+            Ok(state) => Game::new(state),
+            Err(error) => {
+                println!("info string invalid FEN: {}", error);
+                return;
+            }
+        };
+
+        for text in args.get(moves_at + 1..).unwrap_or(&[]) {
+            match game.state.parse_uci_move(text) {
+                Ok(mv) => game.play(mv),
+                Err(error) => {
+                    println!("info string {}", error);
+                    break;
+                }
+            }
+        }
+        self.game = game;
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let mut limits = SearchLimits { multi_pv: self.options.multi_pv, ..Default::default() };
+        let mut clocks = [None, None];
+        let mut increments = [Duration::from_secs(0); 2];
+        let mut moves_to_go = None;
+        let mut infinite = false;
+        let mut ponder = false;
+
+        // Clocks can go negative when a GUI is running late
+        let millis = |value: Option<&&str>| {
+            value.and_then(|value| value.parse::<i64>().ok()).map(|ms| Duration::from_millis(ms.max(0) as u64))
+        };
+        let mut words = args.iter();
+        while let Some(word) = words.next() {
+            match *word {
+                "wtime" => clocks[0] = millis(words.next()),
+                "btime" => clocks[1] = millis(words.next()),
+                "winc" => increments[0] = millis(words.next()).unwrap_or_default(),
+                "binc" => increments[1] = millis(words.next()).unwrap_or_default(),
+                "movestogo" => moves_to_go = words.next().and_then(|moves| moves.parse().ok()),
+                "depth" => limits.depth = words.next().and_then(|depth| depth.parse().ok()),
+                "nodes" => limits.nodes = words.next().and_then(|nodes| nodes.parse().ok()),
+                "movetime" => limits.time = millis(words.next()),
+                "infinite" => infinite = true,
+                "ponder" => ponder = true,
+                _ => {}
+            }
+        }
+
+        let us = self.game.state.side_to_move.index();
+        let budget = clocks[us].map(|remaining| time_budget(remaining, increments[us], moves_to_go));
+        if limits.time.is_none() && !infinite {
+            if ponder {
+                // The clock isn't ours until the GUI says the guess was right
+                self.ponder_budget = budget;
+            } else {
+                limits.time = budget;
+            }
+        }
+
+        // Lower skill levels look less far ahead and settle for moves a bit short of the best
+        let skill = self.options.skill_level;
+        if skill < MAX_SKILL {
+            let skill_depth = 1 + skill / 2;
+            limits.depth = Some(limits.depth.map_or(skill_depth, |depth| depth.min(skill_depth)));
+            limits.multi_pv = limits.multi_pv.max(4);
+        }
+        let roll = self.next_random();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        limits.stop = Some(stop.clone());
+        self.pondering.store(ponder, Ordering::Relaxed);
+        let pondering = self.pondering.clone();
+        let multi_pv = self.options.multi_pv;
+        self.thinker.start(
+            &self.game,
+            limits,
+            move |iteration| print_info(iteration, multi_pv),
+            move |result| {
+                // The GUI has to say stop before it gets a move from an infinite or ponder search,
+                // even if the search ran out of things to look at before then
+                while (infinite || pondering.load(Ordering::Relaxed)) && !stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+
+                let best_move = if skill < MAX_SKILL { weakened_move(&result, skill, roll) } else { result.best_move };
+                match best_move {
+                    Some(best_move) if Some(best_move) == result.best_move && result.pv.len() > 1 => {
+                        println!("bestmove {} ponder {}", best_move, result.pv[1])
+                    }
+                    Some(best_move) => println!("bestmove {}", best_move),
+                    // No legal moves, which a GUI shouldn't ask about
+                    None => println!("bestmove 0000"),
+                }
+            },
+        );
+    }
+
+    // Xorshift, plenty for picking between moves
+    fn next_random(&mut self) -> u64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        self.random
+    }
+}
+
+fn print_info(result: &SearchResult, multi_pv: usize) {
+    let millis = result.time.as_millis() as u64;
+    let nps = result.nodes * 1000 / millis.max(1);
+    let lines = std::iter::once((result.score, &result.pv)).chain(result.alternatives.iter().map(|(score, pv)| (*score, pv)));
+    // With the skill level turned down there can be more lines than the GUI asked for
+    for (index, (score, pv)) in lines.take(multi_pv).enumerate() {
+        let pv: Vec<String> = pv.iter().map(|mv| mv.to_string()).collect();
+        let multi_pv = if multi_pv > 1 { format!(" multipv {}", index + 1) } else { String::new() };
+        println!(
+            "info depth {}{} score {} nodes {} nps {} time {} pv {}",
+            result.depth,
+            multi_pv,
+            score,
+            result.nodes,
+            nps,
+            millis,
+            pv.join(" ")
+        );
+    }
+}
+
+// Scores on one scale, with mates beyond anything else
+fn centipawns(score: Score) -> i32 {
+    match score {
+        Score::Centipawns(cp) => cp,
+        Score::Mate(moves) if moves > 0 => 100_000 - moves,
+        Score::Mate(moves) => -100_000 - moves,
+    }
+}
+
+// Picks one of the moves within reach of the best at random. The lower the skill, the further
+// from the best it'll go.
+fn weakened_move(result: &SearchResult, skill: u32, roll: u64) -> Option<Move> {
+    let best = result.best_move?;
+    let margin = (MAX_SKILL - skill) as i32 * 15;
+    let best_score = centipawns(result.score);
+    let mut candidates = vec![best];
+    for (score, pv) in result.alternatives.iter() {
+        if best_score - centipawns(*score) <= margin {
+            candidates.extend(pv.first());
+        }
+    }
+    Some(candidates[(roll % candidates.len() as u64) as usize])
+}
@@ -0,0 +1,355 @@
+use std::io;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use rust_chess_engine::core::{
+    game_outcome, parse_fen, GameResult, GameState, Move, MoveParseError, PieceColor, Score, SearchLimits, SearchResult,
+    STARTING_BOARD_FEN,
+};
+
+use crate::thinking::*;
+
+// The Chess Engine Communication Protocol, which XBoard, WinBoard and a lot of older tools speak.
+// See https://www.gnu.org/software/xboard/engine-intf.html
+//
+// Unlike UCI the engine keeps track of the game itself and moves on its own once it's its turn,
+// so everything, finished searches included, goes through one queue and gets handled in order.
+
+enum Event {
+    Command(String),
+    // A search for a move to play has finished. The number says which one.
+    Finished(u64, SearchResult),
+}
+
+enum Searching {
+    // Thinking about a move to play
+    Move(u64),
+    // Thinking on the opponent's time, only to fill the transposition table
+    Ponder,
+}
+
+struct Xboard {
+    thinker: Thinker,
+    game: Game,
+    events: Sender<Event>,
+    searching: Option<Searching>,
+    searches: u64,
+    // In force mode we only keep track of the moves, playing neither side
+    force: bool,
+    engine_color: PieceColor,
+    post: bool,
+    ponder: bool,
+    // From sd, st and level
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    moves_per_session: u32,
+    increment: Duration,
+    // What's left on our clock, from time
+    clock: Option<Duration>,
+}
+
+// Takes over from the first line, "xboard", and keeps going until quit
+pub fn run() {
+    let (sender, events) = mpsc::channel();
+    let input = sender.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lines().map_while(Result::ok) {
+            if input.send(Event::Command(line)).is_err() {
+                return;
+            }
+        }
+        // The GUI went away without saying quit
+        let _ = input.send(Event::Command("quit".to_string()));
+    });
+
+    let mut xboard = Xboard::new(sender);
+    for event in events.iter() {
+        match event {
+            Event::Command(line) => {
+                if !xboard.handle(&line) {
+                    return;
+                }
+            }
+            Event::Finished(search, result) => xboard.finished(search, result),
+        }
+    }
+}
+
+impl Xboard {
+    fn new(events: Sender<Event>) -> Self {
+        Xboard {
+            thinker: Thinker::new(),
+            game: Game::new(parse_fen(STARTING_BOARD_FEN).unwrap()),
+            events,
+            searching: None,
+            searches: 0,
+            force: false,
+            engine_color: PieceColor::Black,
+            post: false,
+            ponder: false,
+            depth: None,
+            move_time: None,
+            moves_per_session: 0,
+            increment: Duration::from_secs(0),
+            clock: None,
+        }
+    }
+
+    // Handles one line from the GUI. Returns false once it's time to quit.
+    fn handle(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return true,
+        };
+
+        match command {
+            "protover" => println!(
+                "feature myname=\"Rusty Chess {}\" variants=\"normal\" setboard=1 usermove=1 ping=1 time=1 \
+                 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 done=1",
+                env!("CARGO_PKG_VERSION")
+            ),
+            "new" => {
+                self.stop_thinking();
+                self.thinker.search().clear();
+                self.game = Game::new(parse_fen(STARTING_BOARD_FEN).unwrap());
+                self.force = false;
+                self.engine_color = PieceColor::Black;
+                // Time controls and the depth limit are set again for every game
+                self.depth = None;
+                self.move_time = None;
+                self.moves_per_session = 0;
+                self.increment = Duration::from_secs(0);
+                self.clock = None;
+            }
+            "force" => {
+                self.stop_thinking();
+                self.force = true;
+            }
+            "go" => {
+                self.stop_thinking();
+                self.force = false;
+                self.engine_color = self.game.state.side_to_move;
+                self.think();
+            }
+            "usermove" => match args.first() {
+                Some(text) => self.user_move(text),
+                None => println!("Error (no move given): usermove"),
+            },
+            "setboard" => {
+                self.stop_thinking();
+                match parse_fen(&args.join(" ")) {
+                    Ok(state) => self.game = Game::new(state),
+                    Err(error) => println!("tellusererror Illegal position: {}", error),
+                }
+            }
+            "level" => self.level(args),
+            "st" => match args.first().and_then(|seconds| seconds.parse::<f64>().ok()) {
+                Some(seconds) => self.move_time = Some(Duration::from_secs_f64(seconds.max(0.0))),
+                None => println!("Error (bad time): {}", line),
+            },
+            "sd" => match args.first().and_then(|depth| depth.parse().ok()) {
+                Some(depth) => self.depth = Some(depth),
+                None => println!("Error (bad depth): {}", line),
+            },
+            // Both in centiseconds. How the opponent's doing doesn't change how we spend ours.
+            "time" => match args.first().and_then(|time| time.parse::<i64>().ok()) {
+                Some(time) => self.clock = Some(Duration::from_millis(time.max(0) as u64 * 10)),
+                None => println!("Error (bad time): {}", line),
+            },
+            "otim" => {}
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "hard" => self.ponder = true,
+            "easy" => {
+                self.ponder = false;
+                if let Some(Searching::Ponder) = self.searching {
+                    self.stop_thinking();
+                }
+            }
+            // The game's over, one way or another, until the next new
+            "result" => {
+                self.stop_thinking();
+                self.force = true;
+            }
+            // Move now
+            "?" => {
+                if let Some(Searching::Move(_)) = self.searching {
+                    self.thinker.stop();
+                }
+            }
+            "ping" => println!("pong {}", args.join(" ")),
+            "quit" => {
+                self.stop_thinking();
+                return false;
+            }
+            // Nothing to do for these
+            "xboard" | "accepted" | "rejected" | "random" | "computer" | "name" | "ics" | "hint" | "bk" => {}
+            // GUIs that turned usermove down send moves on their own
+            _ if words.len() == 1 && self.parse_move(command).is_ok() => self.user_move(command),
+            _ => println!("Error (unknown command): {}", command),
+        }
+        true
+    }
+
+    // level <moves per session> <minutes>[:<seconds>] <increment in seconds>
+    fn level(&mut self, args: &[&str]) {
+        let base = args.get(1).and_then(|base| {
+            let mut parts = base.split(':');
+            let minutes: u64 = parts.next()?.parse().ok()?;
+            let seconds: u64 = parts.next().map_or(Some(0), |seconds| seconds.parse().ok())?;
+            Some(minutes * 60 + seconds)
+        });
+        let moves = args.first().and_then(|moves| moves.parse().ok());
+        let increment = args.get(2).and_then(|increment| increment.parse::<f64>().ok());
+        match (moves, base, increment) {
+            (Some(moves), Some(base), Some(increment)) => {
+                self.moves_per_session = moves;
+                self.increment = Duration::from_secs_f64(increment.max(0.0));
+                self.clock = Some(Duration::from_secs(base));
+                // level and st are one or the other
+                self.move_time = None;
+            }
+            _ => println!("Error (bad time control): level {}", args.join(" ")),
+        }
+    }
+
+    // Moves come in coordinate notation, but SAN works too
+    fn parse_move(&self, text: &str) -> Result<Move, MoveParseError> {
+        self.game.state.parse_uci_move(text).or_else(|_| self.game.state.parse_san(text))
+    }
+
+    fn user_move(&mut self, text: &str) {
+        let mv = match self.parse_move(text) {
+            Ok(mv) => mv,
+            Err(_) => {
+                println!("Illegal move: {}", text);
+                return;
+            }
+        };
+        self.stop_thinking();
+        self.game.play(mv);
+        if !self.report_outcome() {
+            self.think();
+        }
+    }
+
+    fn take_back(&mut self, moves: usize) {
+        self.stop_thinking();
+        for _ in 0..moves {
+            if !self.game.undo() {
+                println!("Error (no moves to take back): {}", if moves == 1 { "undo" } else { "remove" });
+                return;
+            }
+        }
+    }
+
+    // Starts thinking about a move if it's our turn
+    fn think(&mut self) {
+        if self.force || self.game.state.side_to_move != self.engine_color {
+            return;
+        }
+
+        let budget = match (self.move_time, self.clock) {
+            (Some(move_time), _) => Some(move_time),
+            (None, Some(remaining)) => {
+                let moves_to_go = match self.moves_per_session {
+                    0 => None,
+                    session => Some(session - (self.game.state.fullmove_number - 1) % session),
+                };
+                Some(time_budget(remaining, self.increment, moves_to_go))
+            }
+            // With no time control at all, only a depth limit stops the search
+            (None, None) => None,
+        };
+        let limits = SearchLimits { depth: self.depth, time: budget, ..Default::default() };
+
+        self.searches += 1;
+        let search = self.searches;
+        self.searching = Some(Searching::Move(search));
+        let events = self.events.clone();
+        let state = self.game.state;
+        let post = self.post;
+        self.thinker.start(
+            &self.game,
+            limits,
+            move |iteration| {
+                if post {
+                    print_thinking(&state, iteration);
+                }
+            },
+            move |result| {
+                let _ = events.send(Event::Finished(search, result));
+            },
+        );
+    }
+
+    fn finished(&mut self, search: u64, result: SearchResult) {
+        // Searches stopped along the way still finish, but their moves aren't wanted
+        match self.searching {
+            Some(Searching::Move(current)) if current == search => self.searching = None,
+            _ => return,
+        }
+        let best_move = match result.best_move {
+            Some(best_move) => best_move,
+            None => return,
+        };
+
+        println!("move {}", best_move);
+        self.game.play(best_move);
+        if self.report_outcome() {
+            return;
+        }
+
+        // Guess the reply from the principal variation and think about where that leaves us, so the
+        // next search starts with a full transposition table if it's right
+        if let (true, Some(reply)) = (self.ponder, result.pv.get(1)) {
+            let mut game = Game::new(self.game.state);
+            game.history = self.game.history.clone();
+            game.play(*reply);
+            self.searching = Some(Searching::Ponder);
+            self.thinker.start(&game, SearchLimits::default(), |_| {}, |_| {});
+        }
+    }
+
+    fn stop_thinking(&mut self) {
+        if self.searching.take().is_some() {
+            self.thinker.stop();
+        }
+    }
+
+    // Tells the GUI if the game just ended, returning whether it did
+    fn report_outcome(&mut self) -> bool {
+        match game_outcome(&self.game.state, &self.game.history) {
+            Some(outcome) => {
+                println!("{} {{{}}}", GameResult::from_outcome(outcome), outcome);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// One line per iteration: depth, score, time in centiseconds, nodes and the principal variation
+fn print_thinking(state: &GameState, result: &SearchResult) {
+    let score = match result.score {
+        Score::Centipawns(cp) => cp,
+        // Mates are 100000 and up, or -100000 and down when it's us getting mated
+        Score::Mate(moves) if moves > 0 => 100_000 + moves,
+        Score::Mate(moves) => -100_000 + moves,
+    };
+    let mut position = *state;
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|mv| {
+            let san = position.move_to_san(*mv);
+            position.make_move(*mv);
+            san
+        })
+        .collect();
+    println!("{} {} {} {} {}", result.depth, score, result.time.as_millis() / 10, result.nodes, pv.join(" "));
+}
@@ -0,0 +1,62 @@
+use std::{env, process, time::Instant};
+
+use rust_chess_engine::core::{parse_fen, perft, perft_divide, STARTING_BOARD_FEN};
+
+// Runs perft from the command line without the GUI:
+//     cargo run --release --no-default-features --bin perft -- [--divide] <depth> [fen]
+// The FEN defaults to the starting position.
+
+fn usage() -> ! {
+    eprintln!("Usage: perft [--divide] <depth> [fen]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let divide = args.first().is_some_and(|arg| arg == "--divide");
+    if divide {
+        args.remove(0);
+    }
+
+    let depth: u32 = match args.first().and_then(|depth| depth.parse().ok()) {
+        Some(depth) => depth,
+        None => usage(),
+    };
+    // The FEN is usually passed as one quoted argument, but gluing the rest back together
+    // means it works unquoted too
+    let fen = if args.len() > 1 {
+        args[1..].join(" ")
+    } else {
+        STARTING_BOARD_FEN.to_string()
+    };
+
+    let mut state = match parse_fen(&fen) {
+        Ok(state) => state,
+        Err(error) => {
+            eprintln!("Invalid FEN: {}", error);
+            process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let nodes = if divide {
+        let divided = perft_divide(&mut state, depth);
+        for (mv, nodes) in divided.iter() {
+            println!("{}: {}", mv, nodes);
+        }
+        println!();
+        println!("Moves: {}", divided.len());
+        divided.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        perft(&mut state, depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("Nodes: {}", nodes);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
@@ -1,38 +1,219 @@
-use bevy::{app::AppExit, prelude::*};
+use bevy::prelude::*;
 use bevy_mod_picking::*;
 
+use std::time::{Duration, SystemTime};
+
+use rust_chess_engine::core::{
+    attacked_squares, game_outcome, generate_legal_moves, is_in_check, king_position, pgn_date, square_bit, square_index,
+    Bitboard, Board, GameOutcome, GameResult, GameState, Move, PgnGame, PieceColor, PieceType,
+};
+
+use crate::move_list::MoveList;
 use crate::pieces::*;
 
 pub struct PlayerTurn(pub PieceColor);
-impl Default for PlayerTurn {
+
+// Whoever the loaded position says is to move goes first
+impl FromResources for PlayerTurn {
+    fn from_resources(resources: &Resources) -> Self {
+        Self(resources.get::<GameState>().map_or(PieceColor::White, |state| state.side_to_move))
+    }
+}
+
+// How the game stands after the last move. Once `outcome` is set the board stops taking moves
+// and the UI shows the result.
+pub struct GameStatus {
+    pub outcome: Option<GameOutcome>,
+    // The king of the side to move, if it's in check
+    pub king_in_check: Option<(u8, u8)>,
+    // The move that got the game here, if there was one
+    pub last_move: Option<Move>,
+    // Every square the side that just moved attacks
+    pub attacked: Bitboard,
+}
+
+impl GameStatus {
+    fn from_game_state(game_state: &GameState, position_history: &PositionHistory, last_move: Option<Move>) -> Self {
+        GameStatus {
+            outcome: game_outcome(game_state, &position_history.0),
+            king_in_check: if is_in_check(game_state) {
+                king_position(&game_state.board, game_state.side_to_move)
+            } else {
+                None
+            },
+            last_move,
+            attacked: attacked_squares(&game_state.board, game_state.side_to_move.opposite()),
+        }
+    }
+}
+
+impl FromResources for GameStatus {
+    fn from_resources(resources: &Resources) -> Self {
+        let state = resources.get::<GameState>().map_or_else(GameState::default, |state| *state);
+        GameStatus::from_game_state(&state, &PositionHistory::default(), None)
+    }
+}
+
+// Optional extras drawn on the board
+#[derive(Default)]
+pub struct HighlightSettings {
+    // Shade the squares the side that just moved attacks. Toggled with the T key.
+    pub show_attacked: bool,
+}
+
+// Every move played so far, in order
+#[derive(Default)]
+pub struct MoveHistory(pub Vec<Move>);
+
+// The position the moves in MoveHistory were played from
+pub struct StartingPosition(pub GameState);
+
+impl FromResources for StartingPosition {
+    fn from_resources(resources: &Resources) -> Self {
+        Self(resources.get::<GameState>().map_or_else(GameState::default, |state| *state))
+    }
+}
+
+// How long was spent on each move in MoveHistory, for the clock comments in saved games
+#[derive(Default)]
+pub struct MoveTimes {
+    pub times: Vec<Duration>,
+    // Seconds since startup when the last move was played, or the game started
+    last_move_at: f64,
+}
+
+// The hash of every position before the current one, in order, for spotting repetitions
+#[derive(Default)]
+pub struct PositionHistory(pub Vec<u64>);
+
+// A pawn move to the last rank that's waiting on the player to pick a piece. The promotion
+// on the stored move is left empty until they do.
+#[derive(Default)]
+pub struct PendingPromotion(pub Option<Move>);
+
+#[derive(Default)]
+pub struct PromotionSettings {
+    // Skip the picker and always promote to a queen. Toggled with the A key.
+    pub auto_queen: bool,
+}
+
+// Sent whenever a move should be played on the board. Anything that wants to make a move
+// goes through this so the game state and the piece entities stay in step. Only the squares
+// and promotion need to be set, the flags get filled in from the legal moves.
+pub struct PlayMoveEvent(pub Move);
+
+// Sent to put the board in a new position all at once: `start` with `moves` played from it.
+// Everything is rebuilt from scratch, so nothing animates.
+pub struct LoadPositionEvent {
+    pub start: GameState,
+    pub moves: Vec<Move>,
+    // How long each move took, if that's known
+    pub times: Vec<Duration>,
+    // Set when stepping around the game's own moves rather than starting something new, so
+    // anything taken back can still be redone afterwards
+    pub review: bool,
+}
+
+// Sent to take back the last move
+pub struct UndoMoveEvent;
+
+// Sent to play the last taken back move again
+pub struct RedoMoveEvent;
+
+// Moves that were taken back, the most recent last. Playing anything other than the next one
+// of these starts a new line and forgets them.
+#[derive(Default)]
+pub struct RedoHistory(pub Vec<Move>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayerKind {
+    // Moves by clicking on the board
+    Human,
+    // The engine picks the moves
+    Computer,
+}
+
+impl PlayerKind {
+    // What goes in a saved game's White and Black tags
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerKind::Human => "Human",
+            PlayerKind::Computer => "Rusty Chess",
+        }
+    }
+}
+
+// Who's playing each side. Clicks only move pieces for the human ones.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Players {
+    pub white: PlayerKind,
+    pub black: PlayerKind,
+}
+
+impl Default for Players {
     fn default() -> Self {
-        Self(PieceColor::White)
+        Players { white: PlayerKind::Human, black: PlayerKind::Human }
     }
 }
 
-impl PlayerTurn {
-    fn change(&mut self) {
-        self.0 = match self.0 {
-            PieceColor::White => PieceColor::Black,
-            PieceColor::Black => PieceColor::White
+impl Players {
+    pub fn get(&self, color: PieceColor) -> PlayerKind {
+        match color {
+            PieceColor::White => self.white,
+            PieceColor::Black => self.black,
+        }
+    }
+
+    pub fn get_mut(&mut self, color: PieceColor) -> &mut PlayerKind {
+        match color {
+            PieceColor::White => &mut self.white,
+            PieceColor::Black => &mut self.black,
         }
     }
 }
 
+// While set, clicks on the board are ignored. Used when showing a game rather than playing one.
+#[derive(Default)]
+pub struct ReadOnlyBoard(pub bool);
+
 pub struct BoardPlugin;
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<SelectedSquare>()
             .init_resource::<SelectedPiece>()
             .init_resource::<PlayerTurn>()
+            .init_resource::<GameStatus>()
+            .init_resource::<MoveHistory>()
+            .init_resource::<PositionHistory>()
+            .init_resource::<StartingPosition>()
+            .init_resource::<MoveTimes>()
+            .init_resource::<RedoHistory>()
+            .init_resource::<PendingPromotion>()
+            .init_resource::<PromotionSettings>()
+            .init_resource::<ReadOnlyBoard>()
+            .init_resource::<HighlightSettings>()
+            .init_resource::<Players>()
             .add_event::<ResetSelectedEvent>()
+            .add_event::<PlayMoveEvent>()
+            .add_event::<LoadPositionEvent>()
+            .add_event::<UndoMoveEvent>()
+            .add_event::<RedoMoveEvent>()
             .add_startup_system(create_board.system())
             .add_system(color_squares.system())
             .add_system(select_square.system())
             .add_system(move_piece.system())
+            .add_system(play_move.system())
+            .add_system(load_position.system())
+            .add_system(undo_move.system())
+            .add_system(redo_move.system())
+            .add_system(undo_redo_keys.system())
             .add_system(select_piece.system())
             .add_system(despawn_taken_pieces.system())
-            .add_system(reset_selected.system());
+            .add_system(reset_selected.system())
+            .add_system(print_fen.system())
+            .add_system(save_pgn.system())
+            .add_system(toggle_auto_queen.system())
+            .add_system(toggle_attacked_squares.system());
     }
 }
 
@@ -43,7 +224,7 @@ pub struct Square {
 
 impl Square {
     fn is_white(&self) -> bool {
-        (self.x + self.y + 1) % 2 == 0
+        (self.x + self.y) % 2 == 1
     }
 }
 #[derive(Default)]
@@ -53,6 +234,8 @@ struct SelectedSquare {
 #[derive(Default)]
 struct SelectedPiece {
     entity: Option<Entity>,
+    // The selected piece's legal moves, worked out once when it's picked up
+    moves: Vec<Move>,
 }
 
 fn create_board(
@@ -91,6 +274,9 @@ them, so this gets those for us. In queries, components have to be references, h
 fn color_squares(
     pick_state: Res<PickState>,
     selected_square: Res<SelectedSquare>,
+    selected_piece: Res<SelectedPiece>,
+    game_status: Res<GameStatus>,
+    highlight_settings: Res<HighlightSettings>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     query: Query<(Entity, &Square, &Handle<StandardMaterial>)>,
 ) {
@@ -105,6 +291,9 @@ fn color_squares(
         // Grab the actual material
         let material = materials.get_mut(material_handle).unwrap();
 
+        // Where the selected piece could go, if anywhere
+        let destination = selected_piece.moves.iter().find(|mv| mv.to == (square.x, square.y));
+
         // Change the material color
         material.albedo = if Some(entity) == top_entity {
             // If hovered
@@ -112,19 +301,54 @@ fn color_squares(
         } else if Some(entity) == selected_square.entity {
             // If selected
             Color::rgb(0.9, 0.1, 0.1)
-        } else if square.is_white() {
-            // If white square
-            Color::rgb(1.0, 0.9, 0.9)
+        } else if destination.is_some_and(|mv| mv.is_capture()) {
+            // If the selected piece can take something there
+            Color::rgb(0.9, 0.5, 0.1)
+        } else if destination.is_some() {
+            // If the selected piece can move there
+            Color::rgb(0.3, 0.7, 0.3)
+        } else if game_status.king_in_check == Some((square.x, square.y)) {
+            // If it's the king in check
+            Color::rgb(1.0, 0.0, 0.0)
         } else {
-            // If black square
-            Color::rgb(0.0, 0.1, 0.1)
+            // The rest are tints over the square's own color, so light and dark squares stay apart
+            let base = if square.is_white() {
+                Color::rgb(1.0, 0.9, 0.9)
+            } else {
+                Color::rgb(0.0, 0.1, 0.1)
+            };
+            let position = (square.x, square.y);
+            if game_status.last_move.is_some_and(|mv| mv.from == position || mv.to == position) {
+                // If the last move came from or went to it
+                tint(base, Color::rgb(0.9, 0.8, 0.1), 0.6)
+            } else if highlight_settings.show_attacked && game_status.attacked & square_bit(square_index(position)) != 0 {
+                // If the side that just moved attacks it
+                tint(base, Color::rgb(0.8, 0.1, 0.5), 0.4)
+            } else {
+                base
+            }
         };
     }
 }
 
+// `amount` of the way from `base` to `over`
+fn tint(base: Color, over: Color, amount: f32) -> Color {
+    Color::rgb(
+        base.r() + (over.r() - base.r()) * amount,
+        base.g() + (over.g() - base.g()) * amount,
+        base.b() + (over.b() - base.b()) * amount,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 fn select_square(
     pick_state: Res<PickState>,
     mouse_button_inputs: Res<Input<MouseButton>>,
+    game_status: Res<GameStatus>,
+    pending_promotion: Res<PendingPromotion>,
+    read_only: Res<ReadOnlyBoard>,
+    players: Res<Players>,
+    turn: Res<PlayerTurn>,
     mut selected_square: ResMut<SelectedSquare>,
     mut selected_piece: ResMut<SelectedPiece>,
     squares_query: Query<&Square>
@@ -134,6 +358,21 @@ fn select_square(
         return;
     }
 
+    // The game is over, or it's only being looked at
+    if game_status.outcome.is_some() || read_only.0 {
+        return;
+    }
+
+    // The computer is moving for this side
+    if players.get(turn.0) == PlayerKind::Computer {
+        return;
+    }
+
+    // Wait for the promotion picker before doing anything else
+    if pending_promotion.0.is_some() {
+        return;
+    }
+
     // Get square under cursor, set as selected
     if let Some((square_entity, _intersection)) = pick_state.top(Group::default()) {
         // Get ref to actual square, making sure it exists and is a square.
@@ -145,6 +384,7 @@ fn select_square(
         // Player clicked outside the board, deselect everything
         selected_square.entity = None;
         selected_piece.entity = None;
+        selected_piece.moves.clear();
     }
 }
 
@@ -152,6 +392,7 @@ fn select_piece(
     selected_square: ChangedRes<SelectedSquare>,
     mut selected_piece: ResMut<SelectedPiece>,
     turn: Res<PlayerTurn>,
+    game_state: Res<GameState>,
     squares_query: Query<&Square>,
     pieces_query: Query<(Entity, &Piece)>
 ) {
@@ -172,20 +413,27 @@ fn select_piece(
         for (piece_entity, piece) in pieces_query.iter() {
             if piece.x == square.x && piece.y == square.y && piece.color == turn.0 {
                 selected_piece.entity = Some(piece_entity);
+                selected_piece.moves = generate_legal_moves(&game_state)
+                    .into_iter()
+                    .filter(|mv| mv.from == (square.x, square.y))
+                    .collect();
                 break;
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn move_piece(
-    commands: &mut Commands,
     selected_square: ChangedRes<SelectedSquare>,
     selected_piece: Res<SelectedPiece>,
-    mut turn: ResMut<PlayerTurn>,
+    game_state: Res<GameState>,
+    promotion_settings: Res<PromotionSettings>,
+    mut pending_promotion: ResMut<PendingPromotion>,
     pieces_metadata: Res<PieceMetadata>,
     squares_query: Query<&Square>,
-    mut pieces_query: Query<(Entity, &mut Piece)>,
+    pieces_query: Query<&Piece>,
+    mut play_move_events: ResMut<Events<PlayMoveEvent>>,
     mut reset_selected_event: ResMut<Events<ResetSelectedEvent>>
 ) {
     if pieces_metadata.piece_is_animating {
@@ -206,41 +454,378 @@ fn move_piece(
     };
 
     if let Some(selected_piece_entity) = selected_piece.entity {
-        let pieces_vec = pieces_query.iter_mut().map(|(_, piece)| *piece).collect();
-        let pieces_entity_vec = pieces_query.iter_mut()
-        .map(|(entity, piece)| (entity, *piece))
-        .collect::<Vec<(Entity, Piece)>>();
-
-        // Move the selected piece to the selected square
-        let mut piece =
-            if let Ok((_piece_entity, piece)) = pieces_query.get_mut(selected_piece_entity) {
-                piece
+        let from = if let Ok(piece) = pieces_query.get(selected_piece_entity) {
+            (piece.x, piece.y)
+        } else {
+            return;
+        };
+
+        // The game state decides what's legal, the entities just follow along
+        let to = (square.x, square.y);
+        let candidates: Vec<Move> = generate_legal_moves(&game_state)
+            .into_iter()
+            .filter(|mv| mv.from == from && mv.to == to)
+            .collect();
+
+        if let Some(mv) = candidates.first() {
+            if mv.promotion.is_none() {
+                play_move_events.send(PlayMoveEvent(*mv));
+            } else if promotion_settings.auto_queen {
+                if let Some(queen_move) = candidates.iter().find(|mv| mv.promotion == Some(PieceType::Queen)) {
+                    play_move_events.send(PlayMoveEvent(*queen_move));
+                }
             } else {
-                return;
-            };
+                // Let the UI ask which piece they want
+                pending_promotion.0 = Some(Move::new(from, to));
+            }
+        }
+
+        reset_selected_event.send(ResetSelectedEvent);
+    }
+}
+
+// Plays each requested move on the game state and mirrors it onto the piece entities
+#[allow(clippy::too_many_arguments)]
+fn play_move(
+    commands: &mut Commands,
+    mut event_reader: Local<EventReader<PlayMoveEvent>>,
+    events: Res<Events<PlayMoveEvent>>,
+    time: Res<Time>,
+    piece_assets: Res<PieceAssets>,
+    mut turn: ResMut<PlayerTurn>,
+    mut game_state: ResMut<GameState>,
+    mut game_status: ResMut<GameStatus>,
+    mut move_history: ResMut<MoveHistory>,
+    mut position_history: ResMut<PositionHistory>,
+    mut move_times: ResMut<MoveTimes>,
+    mut redo_history: ResMut<RedoHistory>,
+    mut pieces_query: Query<(Entity, &mut Piece)>,
+) {
+    for event in event_reader.iter(&events) {
+        // Look the move up again so it has the right flags, and so anything stale gets dropped
+        let mv = match game_state.find_legal_move(event.0.from, event.0.to, event.0.promotion) {
+            Some(mv) => mv,
+            None => continue,
+        };
+
+        let rook_move = mv.castling_rook_move();
+        // En passant takes a pawn that isn't on the square being moved to
+        let captured_square = mv.en_passant_capture_square().unwrap_or(mv.to);
 
-        if piece.is_move_valid((square.x, square.y), pieces_vec) {
-            // Check if a piece of the opposite color exists in this square and despawn it
-            for (other_entity, other_piece) in pieces_entity_vec {
-                if other_piece.x == square.x
-                    && other_piece.y == square.y
-                    && other_piece.color != piece.color {
-                        commands.insert_one(other_entity, Taken);
-                    }
+        for (entity, mut piece) in pieces_query.iter_mut() {
+            if (piece.x, piece.y) == mv.from {
+                if let Some(promotion) = mv.promotion {
+                    // The pawn is swapped out for the piece it promotes to
+                    commands.despawn_recursive(entity);
+                    spawn_piece(commands, &piece_assets, piece.color, promotion, mv.to);
+                } else {
+                    piece.x = mv.to.0;
+                    piece.y = mv.to.1;
+                }
+            } else if (piece.x, piece.y) == captured_square {
+                // A piece of the opposite color is being taken, despawn it
+                commands.insert_one(entity, Taken);
+            } else if let Some(rook_move) = rook_move.filter(|rook_move| (piece.x, piece.y) == rook_move.from) {
+                // Castling, so the rook comes along too
+                piece.x = rook_move.to.0;
+                piece.y = rook_move.to.1;
             }
+        }
+
+        position_history.0.push(game_state.hash);
+        game_state.apply_move(mv);
+        move_history.0.push(mv);
+        let now = time.seconds_since_startup();
+        let spent = Duration::from_secs_f64((now - move_times.last_move_at).max(0.0));
+        move_times.times.push(spent);
+        move_times.last_move_at = now;
+
+        // Redoing a move keeps the rest of the moves to redo, anything else replaces them
+        if redo_history.0.last() == Some(&mv) {
+            redo_history.0.pop();
+        } else {
+            redo_history.0.clear();
+        }
 
-            // Move piece
-            piece.x = square.x;
-            piece.y = square.y;
+        // Change turn
+        turn.0 = game_state.side_to_move;
 
-            // Change turn
-            turn.change();
+        *game_status = GameStatus::from_game_state(&game_state, &position_history, Some(mv));
+        if let Some(outcome) = game_status.outcome {
+            println!("{}. Thanks for playing!", outcome);
         }
+    }
+}
+
+// Plays `moves` from `start` for as long as they're legal, stopping at the first one that isn't.
+// Hands back the position reached, the moves that got played and the hashes of every position
+// before it, oldest first.
+fn replay_legal_moves(start: &GameState, moves: &[Move]) -> (GameState, Vec<Move>, Vec<u64>) {
+    let mut state = *start;
+    let mut played = Vec::new();
+    let mut hashes = Vec::new();
+    for mv in moves.iter() {
+        let mv = match state.find_legal_move(mv.from, mv.to, mv.promotion) {
+            Some(mv) => mv,
+            None => break,
+        };
+        hashes.push(state.hash);
+        state.apply_move(mv);
+        played.push(mv);
+    }
+    (state, played, hashes)
+}
+
+// Swaps the whole board out for the requested position
+#[allow(clippy::too_many_arguments)]
+fn load_position(
+    commands: &mut Commands,
+    mut event_reader: Local<EventReader<LoadPositionEvent>>,
+    events: Res<Events<LoadPositionEvent>>,
+    time: Res<Time>,
+    piece_assets: Res<PieceAssets>,
+    mut turn: ResMut<PlayerTurn>,
+    mut game_state: ResMut<GameState>,
+    mut game_status: ResMut<GameStatus>,
+    mut starting_position: ResMut<StartingPosition>,
+    mut move_history: ResMut<MoveHistory>,
+    mut position_history: ResMut<PositionHistory>,
+    mut move_times: ResMut<MoveTimes>,
+    mut redo_history: ResMut<RedoHistory>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut reset_selected_event: ResMut<Events<ResetSelectedEvent>>,
+    pieces_query: Query<Entity, With<Piece>>,
+) {
+    // Only the latest one matters if a few came in at once
+    let event = match event_reader.iter(&events).last() {
+        Some(event) => event,
+        None => return,
+    };
 
+    let (state, played, hashes) = replay_legal_moves(&event.start, &event.moves);
+    starting_position.0 = event.start;
+    move_history.0 = played;
+    position_history.0 = hashes;
+    // The times go with the moves, so only as many as actually got replayed are kept
+    move_times.times = event.times.clone();
+    move_times.times.truncate(move_history.0.len());
+    move_times.last_move_at = time.seconds_since_startup();
+    if !event.review {
+        redo_history.0.clear();
+    }
+
+    for entity in pieces_query.iter() {
+        commands.despawn_recursive(entity);
+    }
+    for (position, piece) in state.board.pieces() {
+        spawn_piece(commands, &piece_assets, piece.piece_color, piece.piece_type, position);
+    }
+
+    *game_state = state;
+    turn.0 = state.side_to_move;
+    *game_status = GameStatus::from_game_state(&state, &position_history, move_history.0.last().copied());
+    pending_promotion.0 = None;
+    reset_selected_event.send(ResetSelectedEvent);
+}
+
+// Takes back the last move, sliding the pieces back to where they came from
+#[allow(clippy::too_many_arguments)]
+fn undo_move(
+    commands: &mut Commands,
+    mut event_reader: Local<EventReader<UndoMoveEvent>>,
+    events: Res<Events<UndoMoveEvent>>,
+    read_only: Res<ReadOnlyBoard>,
+    piece_assets: Res<PieceAssets>,
+    starting_position: Res<StartingPosition>,
+    mut turn: ResMut<PlayerTurn>,
+    mut game_state: ResMut<GameState>,
+    mut game_status: ResMut<GameStatus>,
+    mut move_history: ResMut<MoveHistory>,
+    mut position_history: ResMut<PositionHistory>,
+    mut move_times: ResMut<MoveTimes>,
+    mut redo_history: ResMut<RedoHistory>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut reset_selected_event: ResMut<Events<ResetSelectedEvent>>,
+    mut pieces_query: Query<(Entity, &mut Piece), Without<Taken>>,
+) {
+    for _event in event_reader.iter(&events) {
+        if read_only.0 {
+            continue;
+        }
+        let mv = match move_history.0.pop() {
+            Some(mv) => mv,
+            None => continue,
+        };
+        position_history.0.pop();
+        move_times.times.pop();
+
+        // Play the game up to just before the move to see what it took
+        let mut before = starting_position.0;
+        for played in move_history.0.iter() {
+            before.apply_move(*played);
+        }
+
+        let rook_move = mv.castling_rook_move();
+        for (entity, mut piece) in pieces_query.iter_mut() {
+            if (piece.x, piece.y) == mv.to {
+                if mv.promotion.is_some() {
+                    // Swap the promoted piece back for a pawn, which then slides back like any other piece
+                    commands.despawn_recursive(entity);
+                    spawn_piece_at(commands, &piece_assets, piece.color, PieceType::Pawn, mv.from, mv.to);
+                } else {
+                    piece.x = mv.from.0;
+                    piece.y = mv.from.1;
+                }
+            } else if let Some(rook_move) = rook_move.filter(|rook_move| (piece.x, piece.y) == rook_move.to) {
+                piece.x = rook_move.from.0;
+                piece.y = rook_move.from.1;
+            }
+        }
+
+        // Put back whatever was taken. En passant took a pawn off a different square.
+        if mv.is_capture() {
+            let captured_square = mv.en_passant_capture_square().unwrap_or(mv.to);
+            if let Some(captured) = before.board.piece_at(captured_square) {
+                spawn_piece(commands, &piece_assets, captured.piece_color, captured.piece_type, captured_square);
+            }
+        }
+
+        *game_state = before;
+        turn.0 = before.side_to_move;
+        *game_status = GameStatus::from_game_state(&before, &position_history, move_history.0.last().copied());
+        redo_history.0.push(mv);
+        pending_promotion.0 = None;
         reset_selected_event.send(ResetSelectedEvent);
     }
 }
 
+// Plays the last taken back move again, through the same path as any other move
+fn redo_move(
+    mut event_reader: Local<EventReader<RedoMoveEvent>>,
+    events: Res<Events<RedoMoveEvent>>,
+    read_only: Res<ReadOnlyBoard>,
+    redo_history: Res<RedoHistory>,
+    mut play_move_events: ResMut<Events<PlayMoveEvent>>,
+) {
+    for _event in event_reader.iter(&events) {
+        if read_only.0 {
+            continue;
+        }
+        if let Some(mv) = redo_history.0.last() {
+            play_move_events.send(PlayMoveEvent(*mv));
+        }
+    }
+}
+
+// Press Z to undo a move and Y to redo it
+fn undo_redo_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut undo_events: ResMut<Events<UndoMoveEvent>>,
+    mut redo_events: ResMut<Events<RedoMoveEvent>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Z) {
+        undo_events.send(UndoMoveEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::Y) {
+        redo_events.send(RedoMoveEvent);
+    }
+}
+
+// Press A to switch auto-queen on or off
+fn toggle_auto_queen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut promotion_settings: ResMut<PromotionSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::A) {
+        promotion_settings.auto_queen = !promotion_settings.auto_queen;
+        println!("Auto-queen: {}", if promotion_settings.auto_queen { "on" } else { "off" });
+    }
+}
+
+// Press T to show or hide the squares the side that just moved attacks
+fn toggle_attacked_squares(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut highlight_settings: ResMut<HighlightSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::T) {
+        highlight_settings.show_attacked = !highlight_settings.show_attacked;
+    }
+}
+
+// Press F to print the FEN of the position on the board. The pieces and whose turn it is come
+// from what's on screen. Castling, en passant and the clocks aren't shown anywhere, so those
+// come from the game state.
+fn print_fen(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<PlayerTurn>,
+    game_state: Res<GameState>,
+    pieces_query: Query<&Piece, Without<Taken>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F) {
+        return;
+    }
+
+    let mut shown = *game_state;
+    shown.board = Board::default();
+    for piece in pieces_query.iter() {
+        let board_piece = rust_chess_engine::core::Piece { piece_type: piece.piece_type, piece_color: piece.color };
+        shown.board.set_piece((piece.x, piece.y), Some(board_piece));
+    }
+    shown.side_to_move = turn.0;
+
+    let fen = shown.to_fen();
+    // The two only differ if the pieces have drifted away from the game they're showing
+    if fen != game_state.to_fen() {
+        eprintln!("The board doesn't match the game, which is at {}", game_state.to_fen());
+    }
+    println!("{}", fen);
+}
+
+// Press S to save the game so far as a PGN file in the working directory
+fn save_pgn(
+    keyboard_input: Res<Input<KeyCode>>,
+    starting_position: Res<StartingPosition>,
+    move_history: Res<MoveHistory>,
+    move_times: Res<MoveTimes>,
+    move_list: Res<MoveList>,
+    players: Res<Players>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    // While reviewing, the board only has the moves up to the one being looked at
+    let (moves, times) = move_list.live_game().unwrap_or((&move_history.0, &move_times.times));
+    let (end, _, hashes) = replay_legal_moves(&starting_position.0, moves);
+    let mut game = PgnGame::new(starting_position.0, moves);
+    game.set_tag("Event", "Casual game");
+    game.set_tag("Site", "Rusty Chess");
+    game.set_tag("Date", &pgn_date(SystemTime::now()));
+    game.set_tag("Round", "-");
+    game.set_tag("White", players.white.name());
+    game.set_tag("Black", players.black.name());
+    game.result = game_outcome(&end, &hashes).map_or(GameResult::Unknown, GameResult::from_outcome);
+    // Games here aren't played against a clock, so it counts up instead: after each move it
+    // reads the time that side has used so far
+    let mut used = [Duration::from_secs(0); 2];
+    let mut mover = starting_position.0.side_to_move;
+    for (pgn_move, time) in game.moves.iter_mut().zip(times.iter()) {
+        used[mover.index()] += *time;
+        pgn_move.elapsed = Some(*time);
+        pgn_move.clock = Some(used[mover.index()]);
+        mover = mover.opposite();
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = format!("rusty-chess-{}.pgn", seconds);
+    match std::fs::write(&path, game.to_string()) {
+        Ok(()) => println!("Saved game to {}", path),
+        Err(error) => eprintln!("Couldn't save game to {}: {}", path, error),
+    }
+}
+
 struct ResetSelectedEvent;
 
 fn reset_selected(
@@ -252,28 +837,16 @@ fn reset_selected(
     for _event in event_reader.iter(&events) {
         selected_square.entity = None;
         selected_piece.entity = None;
+        selected_piece.moves.clear();
     }
 }
 
 struct Taken;
 fn despawn_taken_pieces(
     commands: &mut Commands,
-    mut app_exit_events: ResMut<Events<AppExit>>,
-    query: Query<(Entity, &Piece, &Taken)>,
+    query: Query<(Entity, &Taken)>,
 ) {
-    for (entity, piece, _taken) in query.iter() {
-        // If the king is taken, we should exit
-        if piece.piece_type == PieceType::King {
-            println!(
-                "{} won! Thanks for playing!",
-                match piece.color {
-                    PieceColor::White => "Black",
-                    PieceColor::Black => "White"
-                }
-            );
-            app_exit_events.send(AppExit);
-        }
-
+    for (entity, _taken) in query.iter() {
         commands.despawn_recursive(entity);
     }
 }
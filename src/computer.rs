@@ -0,0 +1,158 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
+use rust_chess_engine::core::{GameState, PieceColor, Search, SearchLimits, SearchResult};
+
+use crate::board::*;
+use crate::pieces::*;
+
+// Moves for whichever sides the engine is playing. The search runs on the async compute task
+// pool so the frame loop keeps going while it thinks, and the move it comes back with is played
+// through PlayMoveEvent like a click would be. W and B switch the engine on or off for white
+// and black.
+
+// A search that's been started and not picked up yet
+struct Thinking {
+    // The position it's looking at, so the answer can be thrown away if the board has moved on
+    hash: u64,
+    moves_played: usize,
+    stop: Arc<AtomicBool>,
+    // Filled in by the task when it's done
+    result: Arc<Mutex<Option<SearchResult>>>,
+}
+
+pub struct Computer {
+    // Shared with the task so the transposition table carries over from move to move
+    search: Arc<Mutex<Search>>,
+    thinking: Option<Thinking>,
+    // How long the engine gets for each move
+    pub think_time: Duration,
+}
+
+impl Computer {
+    fn stop_thinking(&mut self) {
+        if let Some(thinking) = self.thinking.take() {
+            thinking.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn computer_move(
+    task_pool: Res<AsyncComputeTaskPool>,
+    players: Res<Players>,
+    game_state: Res<GameState>,
+    game_status: Res<GameStatus>,
+    move_history: Res<MoveHistory>,
+    position_history: Res<PositionHistory>,
+    read_only: Res<ReadOnlyBoard>,
+    pending_promotion: Res<PendingPromotion>,
+    piece_metadata: Res<PieceMetadata>,
+    mut computer: ResMut<Computer>,
+    mut play_move_events: ResMut<Events<PlayMoveEvent>>,
+) {
+    let computer_to_move = players.get(game_state.side_to_move) == PlayerKind::Computer
+        && game_status.outcome.is_none()
+        && !read_only.0
+        && pending_promotion.0.is_none();
+
+    if let Some(thinking) = &computer.thinking {
+        // Undone, reviewed, loaded over or handed back to a human while it was thinking
+        let stale = thinking.hash != game_state.hash || thinking.moves_played != move_history.0.len();
+        let result = thinking.result.lock().unwrap().take();
+        if stale || !computer_to_move {
+            computer.stop_thinking();
+            return;
+        }
+        if let Some(result) = result {
+            computer.thinking = None;
+            if let Some(best_move) = result.best_move {
+                println!("Computer plays {} ({}, depth {})", best_move, result.score, result.depth);
+                play_move_events.send(PlayMoveEvent(best_move));
+            }
+        }
+        return;
+    }
+
+    // Let the last move finish sliding before starting on the next one
+    if !computer_to_move || piece_metadata.piece_is_animating {
+        return;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let result = Arc::new(Mutex::new(None));
+    let limits = SearchLimits { time: Some(computer.think_time), stop: Some(stop.clone()), ..Default::default() };
+    let search = computer.search.clone();
+    let state = *game_state;
+    let history = position_history.0.clone();
+    let slot = result.clone();
+    task_pool
+        .spawn(async move {
+            let found = search.lock().unwrap().search(&state, &history, &limits, |_| {});
+            *slot.lock().unwrap() = Some(found);
+        })
+        .detach();
+
+    computer.thinking = Some(Thinking { hash: game_state.hash, moves_played: move_history.0.len(), stop, result });
+}
+
+// Against the computer, taking back a move should get back to the human's turn, not leave the
+// computer to play the same move again. So an undo that lands on the computer's turn is followed
+// by another one.
+fn undo_past_computer_moves(
+    mut last_redo_length: Local<usize>,
+    players: Res<Players>,
+    game_state: Res<GameState>,
+    move_history: Res<MoveHistory>,
+    redo_history: Res<RedoHistory>,
+    mut undo_events: ResMut<Events<UndoMoveEvent>>,
+) {
+    // The redo list only gets longer when a move is undone
+    let just_undone = redo_history.0.len() > *last_redo_length;
+    *last_redo_length = redo_history.0.len();
+
+    let us = game_state.side_to_move;
+    if just_undone
+        && !move_history.0.is_empty()
+        && players.get(us) == PlayerKind::Computer
+        && players.get(us.opposite()) == PlayerKind::Human
+    {
+        undo_events.send(UndoMoveEvent);
+    }
+}
+
+// Press W or B to have the computer take over white or black, or hand it back
+fn toggle_computer_players(keyboard_input: Res<Input<KeyCode>>, mut players: ResMut<Players>) {
+    for (key, color) in [(KeyCode::W, PieceColor::White), (KeyCode::B, PieceColor::Black)].iter() {
+        if keyboard_input.just_pressed(*key) {
+            let player = players.get_mut(*color);
+            *player = match player {
+                PlayerKind::Human => PlayerKind::Computer,
+                PlayerKind::Computer => PlayerKind::Human,
+            };
+            println!("{}: {}", color, if *player == PlayerKind::Computer { "computer" } else { "human" });
+        }
+    }
+}
+
+pub struct ComputerPlugin {
+    pub players: Players,
+    pub think_time: Duration,
+}
+
+impl Plugin for ComputerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // Replaces the all-human default BoardPlugin starts with
+        app.add_resource(self.players)
+            .add_resource(Computer {
+                search: Arc::new(Mutex::new(Search::default())),
+                thinking: None,
+                think_time: self.think_time,
+            })
+            .add_system(computer_move.system())
+            .add_system(undo_past_computer_moves.system())
+            .add_system(toggle_computer_players.system());
+    }
+}
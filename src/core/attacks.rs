@@ -0,0 +1,103 @@
+use super::bitboard::*;
+use super::piece::*;
+
+// Everything here is worked out at compile time, so lookups are just array reads.
+
+const fn offsets_table(offsets: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [EMPTY; 64];
+    let mut square = 0;
+    while square < 64 {
+        let rank = (square / 8) as i8;
+        let file = (square % 8) as i8;
+        let mut i = 0;
+        while i < offsets.len() {
+            let target_rank = rank + offsets[i].0;
+            let target_file = file + offsets[i].1;
+            if target_rank >= 0 && target_rank < 8 && target_file >= 0 && target_file < 8 {
+                table[square] |= square_bit((target_rank * 8 + target_file) as u8);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+pub const KNIGHT_ATTACKS: [Bitboard; 64] =
+    offsets_table(&[(2, 1), (2, -1), (-2, 1), (-2, -1), (1, 2), (1, -2), (-1, 2), (-1, -2)]);
+pub const KING_ATTACKS: [Bitboard; 64] =
+    offsets_table(&[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)]);
+// The squares a pawn on each square captures on, indexed by PieceColor::index
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [offsets_table(&[(1, 1), (1, -1)]), offsets_table(&[(-1, 1), (-1, -1)])];
+
+// Sliding directions as (rank, file) steps. The first four step towards higher square indices.
+const DIRECTIONS: [(i8, i8); 8] = [(1, 0), (1, 1), (0, 1), (1, -1), (-1, 0), (-1, -1), (0, -1), (-1, 1)];
+const NORTH: usize = 0;
+const NORTH_EAST: usize = 1;
+const EAST: usize = 2;
+const NORTH_WEST: usize = 3;
+const SOUTH: usize = 4;
+const SOUTH_WEST: usize = 5;
+const WEST: usize = 6;
+const SOUTH_EAST: usize = 7;
+
+// Every square from a square to the edge of the board in each direction, not including the square itself
+const fn rays_table() -> [[Bitboard; 64]; 8] {
+    let mut table = [[EMPTY; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let mut square = 0;
+        while square < 64 {
+            let mut rank = (square / 8) as i8 + DIRECTIONS[direction].0;
+            let mut file = (square % 8) as i8 + DIRECTIONS[direction].1;
+            while rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+                table[direction][square] |= square_bit((rank * 8 + file) as u8);
+                rank += DIRECTIONS[direction].0;
+                file += DIRECTIONS[direction].1;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+const RAYS: [[Bitboard; 64]; 8] = rays_table();
+
+// The ray in `direction`, cut off after the first occupied square it runs into
+fn ray_attacks(square: u8, occupied: Bitboard, direction: usize) -> Bitboard {
+    let ray = RAYS[direction][square as usize];
+    let blockers = ray & occupied;
+    if blockers == EMPTY {
+        return ray;
+    }
+    // The nearest blocker is the lowest bit on rays going up the board and the highest going down
+    let blocker = if direction < SOUTH {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][blocker as usize]
+}
+
+pub fn rook_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    ray_attacks(square, occupied, NORTH)
+        | ray_attacks(square, occupied, EAST)
+        | ray_attacks(square, occupied, SOUTH)
+        | ray_attacks(square, occupied, WEST)
+}
+
+pub fn bishop_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    ray_attacks(square, occupied, NORTH_EAST)
+        | ray_attacks(square, occupied, NORTH_WEST)
+        | ray_attacks(square, occupied, SOUTH_EAST)
+        | ray_attacks(square, occupied, SOUTH_WEST)
+}
+
+pub fn queen_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+pub fn pawn_attacks(square: u8, color: PieceColor) -> Bitboard {
+    PAWN_ATTACKS[color.index()][square as usize]
+}
@@ -0,0 +1,45 @@
+// A set of squares, one bit per square. Bit n is the square with index n = rank * 8 + file,
+// so bit 0 is a1, bit 7 is h1 and bit 63 is h8.
+pub type Bitboard = u64;
+
+pub const EMPTY: Bitboard = 0;
+
+pub const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+pub const FILE_H: Bitboard = FILE_A << 7;
+pub const RANK_1: Bitboard = 0xff;
+pub const RANK_8: Bitboard = RANK_1 << 56;
+// a1 is a dark square, so the light ones are those where rank + file is odd
+pub const LIGHT_SQUARES: Bitboard = 0x55aa_55aa_55aa_55aa;
+
+pub const fn square_bit(square: u8) -> Bitboard {
+    1 << square
+}
+
+pub const fn square_index(pos: (u8, u8)) -> u8 {
+    pos.0 * 8 + pos.1
+}
+
+pub const fn square_position(square: u8) -> (u8, u8) {
+    (square / 8, square % 8)
+}
+
+// Walks the set squares from a1 towards h8
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.0 == EMPTY {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as u8;
+        // Clear the lowest set bit
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
@@ -0,0 +1,102 @@
+use super::bitboard::*;
+use super::piece::*;
+
+pub const DEFAULT_SIDE_LENGTH: u32 = 8;
+
+// Reminder for the small-brained (aka JP): Ranks = rows, file = columns
+// Positions are (rank, file) pairs, both 0-indexed, so (0, 0) is a1 and (7, 7) is h8.
+// Square indices are rank * 8 + file, which is also the bit each square gets in a bitboard.
+//
+// Each square's piece is stored twice: once in the bitboard for its type and once in the
+// bitboard for its color. A piece is wherever those two overlap.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Board {
+    // Indexed by PieceType::index, both colors together
+    pub pieces: [Bitboard; 6],
+    // Indexed by PieceColor::index, all piece types together
+    pub colors: [Bitboard; 2],
+}
+
+pub fn rank_and_file_to_index(rank: u32, file: u32) -> usize {
+    (rank * DEFAULT_SIDE_LENGTH + file) as usize
+}
+
+pub fn index_to_rank_and_file(index: usize) -> (u8, u8) {
+    let rank = index as u32 / DEFAULT_SIDE_LENGTH;
+    let file = index as u32 % DEFAULT_SIDE_LENGTH;
+    (rank as u8, file as u8)
+}
+
+// (rank, file) -> "e4" style square names
+pub fn position_to_algebraic(pos: (u8, u8)) -> String {
+    format!("{}{}", (b'a' + pos.1) as char, pos.0 + 1)
+}
+
+pub fn algebraic_to_position(square: &str) -> Option<(u8, u8)> {
+    let bytes = square.as_bytes();
+    if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1]) {
+        return None;
+    }
+    Some((bytes[1] - b'1', bytes[0] - b'a'))
+}
+
+impl Board {
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    // The squares holding `color`'s pieces of type `piece_type`
+    pub fn pieces_of(&self, color: PieceColor, piece_type: PieceType) -> Bitboard {
+        self.colors[color.index()] & self.pieces[piece_type.index()]
+    }
+
+    pub fn piece_on(&self, square: u8) -> Option<Piece> {
+        let bit = square_bit(square);
+        let piece_color = if self.colors[PieceColor::White.index()] & bit != EMPTY {
+            PieceColor::White
+        } else if self.colors[PieceColor::Black.index()] & bit != EMPTY {
+            PieceColor::Black
+        } else {
+            return None;
+        };
+        let piece_type = *PIECE_TYPES.iter().find(|piece_type| self.pieces[piece_type.index()] & bit != EMPTY)?;
+        Some(Piece { piece_type, piece_color })
+    }
+
+    pub fn set_piece_on(&mut self, square: u8, piece: Option<Piece>) {
+        let bit = square_bit(square);
+        for bitboard in self.pieces.iter_mut().chain(self.colors.iter_mut()) {
+            *bitboard &= !bit;
+        }
+        if let Some(piece) = piece {
+            self.pieces[piece.piece_type.index()] |= bit;
+            self.colors[piece.piece_color.index()] |= bit;
+        }
+    }
+
+    pub fn piece_at(&self, pos: (u8, u8)) -> Option<Piece> {
+        self.piece_on(square_index(pos))
+    }
+
+    pub fn set_piece(&mut self, pos: (u8, u8), piece: Option<Piece>) {
+        self.set_piece_on(square_index(pos), piece);
+    }
+
+    // Every occupied space along with its (rank, file) position
+    pub fn pieces(&self) -> impl Iterator<Item = ((u8, u8), Piece)> + '_ {
+        squares(self.occupied()).filter_map(move |square| {
+            self.piece_on(square).map(|piece| (square_position(square), piece))
+        })
+    }
+
+    pub fn color_of_square(&self, pos: (u8, u8)) -> Option<PieceColor> {
+        let bit = square_bit(square_index(pos));
+        if self.colors[PieceColor::White.index()] & bit != EMPTY {
+            Some(PieceColor::White)
+        } else if self.colors[PieceColor::Black.index()] & bit != EMPTY {
+            Some(PieceColor::Black)
+        } else {
+            None
+        }
+    }
+}
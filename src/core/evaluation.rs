@@ -0,0 +1,295 @@
+use super::attacks::*;
+use super::bitboard::*;
+use super::board::*;
+use super::game_state::*;
+use super::movegen::*;
+use super::piece::*;
+
+// How good a position is, in centipawns. Every term has a middlegame and an endgame weight, and
+// the two are blended by how much material is left ("tapered" evaluation), so for example the
+// king hides in the corner early on but walks to the middle once the queens are off.
+// See https://www.chessprogramming.org/Tapered_Eval
+
+// (middlegame, endgame)
+pub type Weight = (i32, i32);
+
+// Piece-square tables are written the way a board is drawn, rank 8 on top, from white's side.
+// Black's pieces read them upside down.
+pub type PieceSquareTable = [i32; 64];
+
+// Every number the evaluation uses, in one place so they can be tuned. Arrays indexed by piece
+// type follow the order of PIECE_TYPES.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EvalParams {
+    pub material: [Weight; 6],
+    pub middlegame_tables: [PieceSquareTable; 6],
+    pub endgame_tables: [PieceSquareTable; 6],
+    // For every square a piece attacks that isn't its own side's or covered by an enemy pawn
+    pub mobility: [Weight; 6],
+    // For every pawn on a file past the first
+    pub doubled_pawn: Weight,
+    // For a pawn with no pawns of its own on the files next to it
+    pub isolated_pawn: Weight,
+    // For a pawn with no enemy pawns in front of it or on the files next to it, by how many
+    // ranks it's come from the side's own back rank
+    pub passed_pawn: [Weight; 8],
+    // For each pawn on the three files around the king, one and two ranks in front of it
+    pub pawn_shield: [Weight; 2],
+    // For each square next to the king the other side attacks
+    pub king_zone_attack: Weight,
+    // How much each piece counts towards it still being the middlegame, out of `phase_total`
+    pub phase: [i32; 6],
+    pub phase_total: i32,
+}
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: PieceSquareTable = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: PieceSquareTable = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+#[rustfmt::skip]
+const QUEEN: PieceSquareTable = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const BISHOP: PieceSquareTable = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KNIGHT: PieceSquareTable = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const ROOK: PieceSquareTable = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_MIDDLEGAME: PieceSquareTable = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+// Late on, getting a pawn forward matters more than where it stands
+#[rustfmt::skip]
+const PAWN_ENDGAME: PieceSquareTable = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     60,  60,  60,  60,  60,  60,  60,  60,
+     40,  40,  40,  40,  40,  40,  40,  40,
+     25,  25,  25,  25,  25,  25,  25,  25,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      5,   5,   5,   5,   5,   5,   5,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+pub const DEFAULT_EVAL_PARAMS: EvalParams = EvalParams {
+    material: [(0, 0), (900, 920), (330, 320), (320, 300), (500, 520), (100, 120)],
+    middlegame_tables: [KING_MIDDLEGAME, QUEEN, BISHOP, KNIGHT, ROOK, PAWN_MIDDLEGAME],
+    endgame_tables: [KING_ENDGAME, QUEEN, BISHOP, KNIGHT, ROOK, PAWN_ENDGAME],
+    mobility: [(0, 0), (1, 2), (5, 5), (4, 4), (2, 4), (0, 0)],
+    doubled_pawn: (-10, -20),
+    isolated_pawn: (-10, -15),
+    passed_pawn: [(0, 0), (5, 10), (5, 15), (10, 25), (20, 45), (35, 75), (55, 110), (0, 0)],
+    pawn_shield: [(15, 0), (8, 0)],
+    king_zone_attack: (-8, -2),
+    phase: [0, 4, 1, 1, 2, 0],
+    phase_total: 24,
+};
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        DEFAULT_EVAL_PARAMS
+    }
+}
+
+// What a piece is worth on its own, for things like deciding which captures to look at first
+pub fn piece_value(piece_type: PieceType) -> i32 {
+    DEFAULT_EVAL_PARAMS.material[piece_type.index()].0
+}
+
+// How good the position is for the side to move, in centipawns
+pub fn evaluate(state: &GameState) -> i32 {
+    evaluate_with(state, &DEFAULT_EVAL_PARAMS)
+}
+
+pub fn evaluate_with(state: &GameState, params: &EvalParams) -> i32 {
+    let board = &state.board;
+    let (white_middlegame, white_endgame) = side_score(board, PieceColor::White, params);
+    let (black_middlegame, black_endgame) = side_score(board, PieceColor::Black, params);
+
+    let phase: i32 = PIECE_TYPES
+        .iter()
+        .map(|piece_type| board.pieces[piece_type.index()].count_ones() as i32 * params.phase[piece_type.index()])
+        .sum();
+    let phase = phase.min(params.phase_total);
+    let middlegame = white_middlegame - black_middlegame;
+    let endgame = white_endgame - black_endgame;
+    let score = (middlegame * phase + endgame * (params.phase_total - phase)) / params.phase_total.max(1);
+
+    if state.side_to_move == PieceColor::White {
+        score
+    } else {
+        -score
+    }
+}
+
+// The files either side of `file`
+fn adjacent_files(file: u8) -> Bitboard {
+    let file_bitboard = FILE_A << file;
+    ((file_bitboard << 1) & !FILE_A) | ((file_bitboard >> 1) & !FILE_H)
+}
+
+// Every square further up the board than `rank`, from `color`'s side
+fn ranks_ahead(rank: u8, color: PieceColor) -> Bitboard {
+    match color {
+        PieceColor::White if rank < 7 => !0 << ((rank + 1) * 8),
+        PieceColor::Black => (1 << (rank * 8)) - 1,
+        _ => EMPTY,
+    }
+}
+
+// How many ranks `rank` is from `color`'s back rank
+fn relative_rank(rank: u8, color: PieceColor) -> u8 {
+    match color {
+        PieceColor::White => rank,
+        PieceColor::Black => 7 - rank,
+    }
+}
+
+fn add(total: &mut Weight, weight: Weight, times: i32) {
+    total.0 += weight.0 * times;
+    total.1 += weight.1 * times;
+}
+
+// Everything `color` has going for it, before the middlegame and endgame are blended
+fn side_score(board: &Board, color: PieceColor, params: &EvalParams) -> Weight {
+    let mut score = (0, 0);
+    let ours = board.colors[color.index()];
+    let occupied = board.occupied();
+    let our_pawns = board.pieces_of(color, PieceType::Pawn);
+    let their_pawns = board.pieces_of(color.opposite(), PieceType::Pawn);
+
+    let mut covered_by_their_pawns = EMPTY;
+    for square in squares(their_pawns) {
+        covered_by_their_pawns |= pawn_attacks(square, color.opposite());
+    }
+
+    for piece_type in PIECE_TYPES.iter() {
+        let index = piece_type.index();
+        for square in squares(board.pieces_of(color, *piece_type)) {
+            // The tables are drawn rank 8 first, so white's squares need flipping to find their entry
+            let table_square = match color {
+                PieceColor::White => square ^ 56,
+                PieceColor::Black => square,
+            } as usize;
+            add(&mut score, params.material[index], 1);
+            score.0 += params.middlegame_tables[index][table_square];
+            score.1 += params.endgame_tables[index][table_square];
+
+            let attacks = match piece_type {
+                PieceType::Knight => KNIGHT_ATTACKS[square as usize],
+                PieceType::Bishop => bishop_attacks(square, occupied),
+                PieceType::Rook => rook_attacks(square, occupied),
+                PieceType::Queen => queen_attacks(square, occupied),
+                PieceType::King | PieceType::Pawn => EMPTY,
+            };
+            let reachable = attacks & !ours & !covered_by_their_pawns;
+            add(&mut score, params.mobility[index], reachable.count_ones() as i32);
+        }
+    }
+
+    for square in squares(our_pawns) {
+        let (rank, file) = square_position(square);
+        let file_bitboard = FILE_A << file;
+        if our_pawns & adjacent_files(file) == EMPTY {
+            add(&mut score, params.isolated_pawn, 1);
+        }
+        if their_pawns & (file_bitboard | adjacent_files(file)) & ranks_ahead(rank, color) == EMPTY {
+            add(&mut score, params.passed_pawn[relative_rank(rank, color) as usize], 1);
+        }
+    }
+    for file in 0..8 {
+        let on_file = (our_pawns & (FILE_A << file)).count_ones() as i32;
+        if on_file > 1 {
+            add(&mut score, params.doubled_pawn, on_file - 1);
+        }
+    }
+
+    if let Some(king) = king_square(board, color) {
+        let (rank, file) = square_position(king);
+        let shield_files = (FILE_A << file) | adjacent_files(file);
+        for (distance, weight) in params.pawn_shield.iter().enumerate() {
+            let shield_rank = match color {
+                PieceColor::White => rank as i32 + 1 + distance as i32,
+                PieceColor::Black => rank as i32 - 1 - distance as i32,
+            };
+            if (0..8).contains(&shield_rank) {
+                let shield = our_pawns & shield_files & (RANK_1 << (shield_rank * 8));
+                add(&mut score, *weight, shield.count_ones() as i32);
+            }
+        }
+
+        let zone = KING_ATTACKS[king as usize] | square_bit(king);
+        let attacked = zone & attacked_squares(board, color.opposite());
+        add(&mut score, params.king_zone_attack, attacked.count_ones() as i32);
+    }
+
+    score
+}
@@ -0,0 +1,329 @@
+use std::{fmt, str::FromStr};
+
+use super::board::*;
+use super::game_state::*;
+use super::piece::*;
+
+pub const STARTING_BOARD_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+pub const OTHER_TEST_FEN: &str = "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2";
+pub const OTHER_OTHER_TEST_FEN: &str = "r1b1k1nr/p2p1pNp/n2B4/1p1NP2P/6P1/3P1Q2/P1P1K3/q5b1 w KQkq - 0 10";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FenField {
+    PiecePlacement,
+    SideToMove,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            FenField::PiecePlacement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::Castling => "castling ability",
+            FenField::EnPassant => "en passant target square",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FenError {
+    // One of the first four fields isn't there
+    MissingField(FenField),
+    // More than six space separated fields
+    TooManyFields(usize),
+    // A character that doesn't belong in the given field
+    InvalidCharacter { field: FenField, character: char },
+    // The placement field doesn't describe exactly 8 ranks
+    WrongRankCount(usize),
+    // A rank doesn't add up to 8 files. `rank` is 0-indexed from rank 1.
+    WrongRankLength { rank: u8, files: u32 },
+    // The clocks have to be non-negative whole numbers
+    InvalidNumber { field: FenField, value: String },
+    // Pawns can't stand on the first or last rank. Same indexing as WrongRankLength.
+    PawnOnBackRank { rank: u8, file: u8 },
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {} field", field),
+            FenError::TooManyFields(count) => write!(f, "expected at most 6 fields, found {}", count),
+            FenError::InvalidCharacter { field, character } => {
+                write!(f, "invalid character '{}' in {} field", character, field)
+            }
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::WrongRankLength { rank, files } => {
+                write!(f, "rank {} describes {} files instead of 8", rank + 1, files)
+            }
+            FenError::InvalidNumber { field, value } => {
+                write!(f, "'{}' is not a valid {}", value, field)
+            }
+            FenError::PawnOnBackRank { rank, file } => {
+                write!(f, "pawn on {}, which is a back rank", position_to_algebraic((*rank, *file)))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/*  Separate different parts of the fen string. If the string is properly formatted, the parts and
+    their matching indices are:
+    0: Piece Placement
+    1: Side to move ('w' if white's move, 'b' if black's move)
+    2: Castling Ability
+    3: En passant target square
+    4: Halfmove Clock
+    5: Fullmove counter - Number of full moves in a game, incremented after each black move.
+    The two clocks are often left off, so they default to 0 and 1 when missing.
+    For more formatting info, see https://www.chessprogramming.org/Forsyth-Edwards_Notation
+*/
+pub fn parse_fen(fen_string: &str) -> Result<GameState, FenError> {
+    let fen_parts: Vec<&str> = fen_string.split_whitespace().collect();
+    if fen_parts.len() > 6 {
+        return Err(FenError::TooManyFields(fen_parts.len()));
+    }
+    let field = |index: usize, field: FenField| fen_parts.get(index).copied().ok_or(FenError::MissingField(field));
+
+    let mut state = GameState {
+        board: parse_piece_placement(field(0, FenField::PiecePlacement)?)?,
+        side_to_move: parse_side_to_move(field(1, FenField::SideToMove)?)?,
+        castling: parse_castling(field(2, FenField::Castling)?)?,
+        en_passant: parse_en_passant(field(3, FenField::EnPassant)?)?,
+        halfmove_clock: parse_number(fen_parts.get(4).copied(), FenField::HalfmoveClock, 0)?,
+        fullmove_number: parse_number(fen_parts.get(5).copied(), FenField::FullmoveNumber, 1)?,
+        hash: 0,
+    };
+    state.hash = state.compute_hash();
+    Ok(state)
+}
+
+impl FromStr for GameState {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_fen(s)
+    }
+}
+
+fn parse_piece_placement(placement: &str) -> Result<Board, FenError> {
+    let mut board = Board::default();
+
+    // NOTE on endianness of FEN string:
+    // Ranks are big-endian, so 8 -> 1
+    // Files are little-endian, so A -> H
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    for (rank_index, rank_string) in ranks.iter().enumerate() {
+        let rank = 7 - rank_index as u8;
+        let mut file: u32 = 0;
+        for c in rank_string.chars() {
+            if let Some(num_to_skip) = c.to_digit(10) {
+                if num_to_skip == 0 || num_to_skip > 8 {
+                    return Err(FenError::InvalidCharacter { field: FenField::PiecePlacement, character: c });
+                }
+                file += num_to_skip;
+            } else {
+                let piece = piece_from_char(c).ok_or(FenError::InvalidCharacter {
+                    field: FenField::PiecePlacement,
+                    character: c,
+                })?;
+                if piece.piece_type == PieceType::Pawn && (rank == 0 || rank == 7) && file < 8 {
+                    return Err(FenError::PawnOnBackRank { rank, file: file as u8 });
+                }
+                if file < 8 {
+                    board.set_piece((rank, file as u8), Some(piece));
+                }
+                file += 1;
+            }
+        }
+        if file != 8 {
+            return Err(FenError::WrongRankLength { rank, files: file });
+        }
+    }
+
+    Ok(board)
+}
+
+fn parse_side_to_move(side: &str) -> Result<PieceColor, FenError> {
+    match side {
+        "w" => Ok(PieceColor::White),
+        "b" => Ok(PieceColor::Black),
+        _ => Err(FenError::InvalidCharacter {
+            field: FenField::SideToMove,
+            character: first_bad_char(side, |c| c == 'w' || c == 'b'),
+        }),
+    }
+}
+
+fn parse_castling(castling: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::none();
+    if castling == "-" {
+        return Ok(rights);
+    }
+    for c in castling.chars() {
+        let right = match c {
+            'K' => &mut rights.white_kingside,
+            'Q' => &mut rights.white_queenside,
+            'k' => &mut rights.black_kingside,
+            'q' => &mut rights.black_queenside,
+            _ => return Err(FenError::InvalidCharacter { field: FenField::Castling, character: c }),
+        };
+        // Each right can only be listed once
+        if *right {
+            return Err(FenError::InvalidCharacter { field: FenField::Castling, character: c });
+        }
+        *right = true;
+    }
+    Ok(rights)
+}
+
+fn parse_en_passant(en_passant: &str) -> Result<Option<(u8, u8)>, FenError> {
+    if en_passant == "-" {
+        return Ok(None);
+    }
+    let mut chars = en_passant.chars();
+    let file = chars.next().unwrap_or('-');
+    if !('a'..='h').contains(&file) {
+        return Err(FenError::InvalidCharacter { field: FenField::EnPassant, character: file });
+    }
+    // The target square is always behind a pawn that just moved two squares, so it's on rank 3 or 6
+    let rank = chars.next().unwrap_or('-');
+    if rank != '3' && rank != '6' {
+        return Err(FenError::InvalidCharacter { field: FenField::EnPassant, character: rank });
+    }
+    if let Some(c) = chars.next() {
+        return Err(FenError::InvalidCharacter { field: FenField::EnPassant, character: c });
+    }
+    Ok(algebraic_to_position(en_passant))
+}
+
+fn parse_number(value: Option<&str>, field: FenField, default: u32) -> Result<u32, FenError> {
+    match value {
+        None => Ok(default),
+        Some(value) => value.parse().map_err(|_| FenError::InvalidNumber {
+            field,
+            value: value.to_string(),
+        }),
+    }
+}
+
+pub fn piece_from_char(c: char) -> Option<Piece> {
+    let piece_type = match c.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+    let piece_color = if c.is_uppercase() {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    };
+    Some(Piece { piece_type, piece_color })
+}
+
+pub fn piece_to_char(piece: Piece) -> char {
+    let c = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match piece.piece_color {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
+
+impl Board {
+    // Just the piece placement field, rank 8 first
+    pub fn to_fen_placement(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..8).rev() {
+            let mut empty_files = 0;
+            for file in 0..8 {
+                match self.piece_at((rank, file)) {
+                    Some(piece) => {
+                        if empty_files > 0 {
+                            placement.push_str(&empty_files.to_string());
+                            empty_files = 0;
+                        }
+                        placement.push(piece_to_char(piece));
+                    }
+                    None => empty_files += 1,
+                }
+            }
+            if empty_files > 0 {
+                placement.push_str(&empty_files.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+        placement
+    }
+}
+
+impl GameState {
+    // All six fields, in the same format `parse_fen` reads
+    pub fn to_fen(&self) -> String {
+        let side_to_move = match self.side_to_move {
+            PieceColor::White => "w",
+            PieceColor::Black => "b",
+        };
+
+        let mut castling = String::new();
+        if self.castling.white_kingside {
+            castling.push('K');
+        }
+        if self.castling.white_queenside {
+            castling.push('Q');
+        }
+        if self.castling.black_kingside {
+            castling.push('k');
+        }
+        if self.castling.black_queenside {
+            castling.push('q');
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self.en_passant.map_or("-".to_string(), position_to_algebraic);
+
+        format!(
+            "{} {} {} {} {} {}",
+            self.board.to_fen_placement(),
+            side_to_move,
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+// Used to point at the offending character when a whole field is wrong
+fn first_bad_char(value: &str, is_valid: impl Fn(char) -> bool) -> char {
+    value
+        .chars()
+        .find(|c| !is_valid(*c))
+        .or_else(|| value.chars().nth(1))
+        .unwrap_or(' ')
+}
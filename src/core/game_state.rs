@@ -0,0 +1,57 @@
+use super::board::*;
+use super::piece::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    pub fn all() -> Self {
+        CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+
+    pub fn none() -> Self {
+        CastlingRights::default()
+    }
+}
+
+// Everything a FEN string describes: the pieces plus the game-level state that goes with them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameState {
+    pub board: Board,
+    pub side_to_move: PieceColor,
+    pub castling: CastlingRights,
+    // The square a pawn skipped over with a double push last move, if any
+    pub en_passant: Option<(u8, u8)>,
+    // Half moves since the last capture or pawn move, for the fifty-move rule
+    pub halfmove_clock: u32,
+    // Starts at 1 and goes up after each black move
+    pub fullmove_number: u32,
+    // Zobrist hash of everything above except the clocks. make_move keeps it in sync,
+    // anything that changes the fields directly has to call compute_hash.
+    pub hash: u64,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState {
+            board: Board::default(),
+            side_to_move: PieceColor::White,
+            castling: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            // An empty board with white to move and nothing else set hashes to nothing
+            hash: 0,
+        }
+    }
+}
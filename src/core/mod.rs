@@ -0,0 +1,31 @@
+mod attacks;
+mod bitboard;
+mod board;
+mod evaluation;
+mod fen;
+mod game_state;
+mod movegen;
+mod moves;
+mod notation;
+mod outcome;
+mod perft;
+mod pgn;
+mod piece;
+mod search;
+mod zobrist;
+
+pub use attacks::*;
+pub use bitboard::*;
+pub use board::*;
+pub use evaluation::*;
+pub use fen::*;
+pub use game_state::*;
+pub use movegen::*;
+pub use moves::*;
+pub use notation::*;
+pub use outcome::*;
+pub use perft::*;
+pub use pgn::*;
+pub use piece::*;
+pub use search::*;
+pub use zobrist::*;
@@ -0,0 +1,208 @@
+use super::attacks::*;
+use super::bitboard::*;
+use super::board::*;
+use super::game_state::*;
+use super::moves::*;
+use super::piece::*;
+
+// Whether any piece of color `by` could capture on `square`
+pub fn is_square_index_attacked(board: &Board, square: u8, by: PieceColor) -> bool {
+    let occupied = board.occupied();
+    let index = square as usize;
+
+    // Look outwards from the square with each piece's moves. If that lands on an enemy piece
+    // that moves the same way, it can get back here.
+    if KNIGHT_ATTACKS[index] & board.pieces_of(by, PieceType::Knight) != EMPTY {
+        return true;
+    }
+    if KING_ATTACKS[index] & board.pieces_of(by, PieceType::King) != EMPTY {
+        return true;
+    }
+    // Pawns capture towards the side they're moving to, so look the other way
+    if PAWN_ATTACKS[by.opposite().index()][index] & board.pieces_of(by, PieceType::Pawn) != EMPTY {
+        return true;
+    }
+
+    let queens = board.pieces_of(by, PieceType::Queen);
+    let rooks = board.pieces_of(by, PieceType::Rook) | queens;
+    let bishops = board.pieces_of(by, PieceType::Bishop) | queens;
+    rook_attacks(square, occupied) & rooks != EMPTY || bishop_attacks(square, occupied) & bishops != EMPTY
+}
+
+pub fn is_square_attacked(board: &Board, pos: (u8, u8), by: PieceColor) -> bool {
+    is_square_index_attacked(board, square_index(pos), by)
+}
+
+// Every square `color` attacks, whether or not there's anything on it
+pub fn attacked_squares(board: &Board, color: PieceColor) -> Bitboard {
+    let occupied = board.occupied();
+    let mut attacked = EMPTY;
+    for square in squares(board.colors[color.index()]) {
+        attacked |= match board.piece_on(square).map(|piece| piece.piece_type) {
+            Some(PieceType::Knight) => KNIGHT_ATTACKS[square as usize],
+            Some(PieceType::King) => KING_ATTACKS[square as usize],
+            Some(PieceType::Pawn) => pawn_attacks(square, color),
+            Some(PieceType::Bishop) => bishop_attacks(square, occupied),
+            Some(PieceType::Rook) => rook_attacks(square, occupied),
+            Some(PieceType::Queen) => queen_attacks(square, occupied),
+            None => EMPTY,
+        };
+    }
+    attacked
+}
+
+pub fn king_square(board: &Board, color: PieceColor) -> Option<u8> {
+    let kings = board.pieces_of(color, PieceType::King);
+    if kings == EMPTY {
+        None
+    } else {
+        Some(kings.trailing_zeros() as u8)
+    }
+}
+
+pub fn king_position(board: &Board, color: PieceColor) -> Option<(u8, u8)> {
+    king_square(board, color).map(square_position)
+}
+
+// Whether the side to move has its king attacked
+pub fn is_in_check(state: &GameState) -> bool {
+    king_square(&state.board, state.side_to_move)
+        .is_some_and(|king| is_square_index_attacked(&state.board, king, state.side_to_move.opposite()))
+}
+
+fn push_moves(from: u8, targets: Bitboard, enemies: Bitboard, moves: &mut Vec<Move>) {
+    for to in squares(targets) {
+        let flags = if enemies & square_bit(to) != EMPTY { MoveFlags::CAPTURE } else { MoveFlags::QUIET };
+        moves.push(Move::with_flags(square_position(from), square_position(to), flags));
+    }
+}
+
+// Every move the pieces of the side to move can make, ignoring whether it leaves their king in check
+pub fn generate_pseudo_legal_moves(state: &GameState) -> Vec<Move> {
+    let board = &state.board;
+    let color = state.side_to_move;
+    let own = board.colors[color.index()];
+    let enemies = board.colors[color.opposite().index()];
+    let occupied = board.occupied();
+    let mut moves = Vec::with_capacity(64);
+
+    // Moves onto an empty square or an enemy piece
+    for from in squares(board.pieces_of(color, PieceType::Knight)) {
+        push_moves(from, KNIGHT_ATTACKS[from as usize] & !own, enemies, &mut moves);
+    }
+    for from in squares(board.pieces_of(color, PieceType::Bishop)) {
+        push_moves(from, bishop_attacks(from, occupied) & !own, enemies, &mut moves);
+    }
+    for from in squares(board.pieces_of(color, PieceType::Rook)) {
+        push_moves(from, rook_attacks(from, occupied) & !own, enemies, &mut moves);
+    }
+    for from in squares(board.pieces_of(color, PieceType::Queen)) {
+        push_moves(from, queen_attacks(from, occupied) & !own, enemies, &mut moves);
+    }
+    for from in squares(board.pieces_of(color, PieceType::King)) {
+        push_moves(from, KING_ATTACKS[from as usize] & !own, enemies, &mut moves);
+        add_castling_moves(state, square_position(from), &mut moves);
+    }
+    for from in squares(board.pieces_of(color, PieceType::Pawn)) {
+        add_pawn_moves(board, from, color, state.en_passant, &mut moves);
+    }
+
+    moves
+}
+
+fn add_pawn_moves(
+    board: &Board,
+    from: u8,
+    color: PieceColor,
+    en_passant: Option<(u8, u8)>,
+    moves: &mut Vec<Move>,
+) {
+    let occupied = board.occupied();
+    // White pawns move up the ranks, black pawns move down them
+    let (step, start_rank, last_rank): (i8, u8, u8) = match color {
+        PieceColor::White => (8, 1, 7),
+        PieceColor::Black => (-8, 6, 0),
+    };
+    let from_pos = square_position(from);
+
+    // Reaching the last rank means picking a new piece, so each choice is its own move
+    let mut add_move = |to: u8, flags: MoveFlags| {
+        let to = square_position(to);
+        if to.0 == last_rank {
+            for promotion in PROMOTION_PIECES.iter() {
+                moves.push(Move { from: from_pos, to, promotion: Some(*promotion), flags });
+            }
+        } else {
+            moves.push(Move::with_flags(from_pos, to, flags));
+        }
+    };
+
+    // Normal move, and two moves from the starting rank if both squares are free.
+    // parse_fen turns down pawns on the back ranks, so one step forward is always on the board.
+    let one_step = (from as i8 + step) as u8;
+    if occupied & square_bit(one_step) == EMPTY {
+        add_move(one_step, MoveFlags::QUIET);
+        let two_step = (one_step as i8 + step) as u8;
+        if from_pos.0 == start_rank && occupied & square_bit(two_step) == EMPTY {
+            add_move(two_step, MoveFlags::DOUBLE_PAWN_PUSH);
+        }
+    }
+
+    // Take a piece, either on the square itself or en passant behind a pawn that just moved two
+    let attacks = pawn_attacks(from, color);
+    for to in squares(attacks & board.colors[color.opposite().index()]) {
+        add_move(to, MoveFlags::CAPTURE);
+    }
+    if let Some(en_passant) = en_passant {
+        let to = square_index(en_passant);
+        if attacks & square_bit(to) != EMPTY {
+            add_move(to, MoveFlags::CAPTURE | MoveFlags::EN_PASSANT);
+        }
+    }
+}
+
+fn add_castling_moves(state: &GameState, from: (u8, u8), moves: &mut Vec<Move>) {
+    let board = &state.board;
+    let color = state.side_to_move;
+    let (rank, kingside, queenside) = match color {
+        PieceColor::White => (0, state.castling.white_kingside, state.castling.white_queenside),
+        PieceColor::Black => (7, state.castling.black_kingside, state.castling.black_queenside),
+    };
+
+    // The rights only mean something with the king and rook still on their home squares
+    if from != (rank, 4) {
+        return;
+    }
+    let rook = Some(Piece { piece_type: PieceType::Rook, piece_color: color });
+    let is_empty = |files: &[u8]| files.iter().all(|file| board.piece_at((rank, *file)).is_none());
+    // The king can't castle out of, through or into check
+    let is_safe = |files: &[u8]| {
+        files
+            .iter()
+            .all(|file| !is_square_attacked(board, (rank, *file), color.opposite()))
+    };
+
+    if kingside && board.piece_at((rank, 7)) == rook && is_empty(&[5, 6]) && is_safe(&[4, 5, 6]) {
+        moves.push(Move::with_flags(from, (rank, 6), MoveFlags::CASTLE));
+    }
+    // The b-file only has to be empty, the king never crosses it
+    if queenside && board.piece_at((rank, 0)) == rook && is_empty(&[1, 2, 3]) && is_safe(&[4, 3, 2]) {
+        moves.push(Move::with_flags(from, (rank, 2), MoveFlags::CASTLE));
+    }
+}
+
+// Every move the side to move can make without leaving its own king in check
+pub fn generate_legal_moves(state: &GameState) -> Vec<Move> {
+    let color = state.side_to_move;
+    let mut scratch = *state;
+    generate_pseudo_legal_moves(state)
+        .into_iter()
+        .filter(|mv| {
+            let undo = scratch.make_move(*mv);
+            let is_legal = king_square(&scratch.board, color)
+                .is_none_or(|king| !is_square_index_attacked(&scratch.board, king, color.opposite()));
+            scratch.unmake_move(*mv, undo);
+            is_legal
+        })
+        .collect()
+}
@@ -0,0 +1,278 @@
+use std::{fmt, ops::BitOr};
+
+use super::bitboard::*;
+use super::board::*;
+use super::game_state::*;
+use super::movegen::*;
+use super::piece::*;
+use super::zobrist::*;
+
+// Extra facts about a move that can't be seen from the squares alone.
+// The move generator fills these in, and make_move relies on them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MoveFlags(u8);
+
+impl MoveFlags {
+    pub const QUIET: MoveFlags = MoveFlags(0);
+    pub const CAPTURE: MoveFlags = MoveFlags(1);
+    pub const DOUBLE_PAWN_PUSH: MoveFlags = MoveFlags(1 << 1);
+    // Always comes with CAPTURE too
+    pub const EN_PASSANT: MoveFlags = MoveFlags(1 << 2);
+    pub const CASTLE: MoveFlags = MoveFlags(1 << 3);
+
+    pub fn contains(&self, other: MoveFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for MoveFlags {
+    type Output = MoveFlags;
+
+    fn bitor(self, other: MoveFlags) -> MoveFlags {
+        MoveFlags(self.0 | other.0)
+    }
+}
+
+// A move of the piece on `from` to `to`, both (rank, file) positions
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Move {
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+    // What a pawn reaching the last rank turns into
+    pub promotion: Option<PieceType>,
+    pub flags: MoveFlags,
+}
+
+pub const PROMOTION_PIECES: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+impl Move {
+    pub fn new(from: (u8, u8), to: (u8, u8)) -> Self {
+        Move { from, to, promotion: None, flags: MoveFlags::QUIET }
+    }
+
+    pub fn with_flags(from: (u8, u8), to: (u8, u8), flags: MoveFlags) -> Self {
+        Move { from, to, promotion: None, flags }
+    }
+
+    pub fn with_promotion(from: (u8, u8), to: (u8, u8), promotion: PieceType) -> Self {
+        Move { from, to, promotion: Some(promotion), flags: MoveFlags::QUIET }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.flags.contains(MoveFlags::CAPTURE)
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.flags.contains(MoveFlags::CASTLE)
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.flags.contains(MoveFlags::EN_PASSANT)
+    }
+
+    // If this is the king castling, the move the rook makes alongside it
+    pub fn castling_rook_move(&self) -> Option<Move> {
+        if !self.is_castle() {
+            return None;
+        }
+        let rank = self.from.0;
+        Some(if self.to.1 > self.from.1 {
+            // Kingside: h-file rook goes to f
+            Move::new((rank, 7), (rank, 5))
+        } else {
+            // Queenside: a-file rook goes to d
+            Move::new((rank, 0), (rank, 3))
+        })
+    }
+
+    // If this is a pawn taking en passant, where the pawn it takes is standing.
+    // The bypassed pawn is beside the one taking it, on the file it's moving to.
+    pub fn en_passant_capture_square(&self) -> Option<(u8, u8)> {
+        if self.is_en_passant() {
+            Some((self.from.0, self.to.1))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", position_to_algebraic(self.from), position_to_algebraic(self.to))?;
+        if let Some(promotion) = self.promotion {
+            let c = match promotion {
+                PieceType::Queen => 'q',
+                PieceType::Rook => 'r',
+                PieceType::Bishop => 'b',
+                PieceType::Knight => 'n',
+                PieceType::King => 'k',
+                PieceType::Pawn => 'p',
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+// Everything make_move throws away that unmake_move needs to put the position back exactly
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Undo {
+    pub captured: Option<Piece>,
+    pub castling: CastlingRights,
+    pub en_passant: Option<(u8, u8)>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    pub hash: u64,
+}
+
+// Anything leaving or landing on a king or rook home square loses the rights tied to it.
+// That covers the king moving, a rook moving and a rook getting captured.
+fn revoke_castling_rights(castling: &mut CastlingRights, square: u8) {
+    match square {
+        4 => {
+            castling.white_kingside = false;
+            castling.white_queenside = false;
+        }
+        7 => castling.white_kingside = false,
+        0 => castling.white_queenside = false,
+        60 => {
+            castling.black_kingside = false;
+            castling.black_queenside = false;
+        }
+        63 => castling.black_kingside = false,
+        56 => castling.black_queenside = false,
+        _ => {}
+    }
+}
+
+impl Board {
+    // Flips `piece` on or off `square` without looking at what's there
+    fn toggle_piece(&mut self, square: u8, piece: Piece) {
+        let bit = square_bit(square);
+        self.pieces[piece.piece_type.index()] ^= bit;
+        self.colors[piece.piece_color.index()] ^= bit;
+    }
+}
+
+impl GameState {
+    // Moves a piece on or off the board and keeps the hash in step
+    fn toggle_piece(&mut self, square: u8, piece: Piece) {
+        self.board.toggle_piece(square, piece);
+        self.hash ^= piece_key(piece, square);
+    }
+
+    // Plays `mv`, which has to have come out of the move generator, and hands back what's
+    // needed to take it back with `unmake_move`.
+    pub fn make_move(&mut self, mv: Move) -> Undo {
+        let from = square_index(mv.from);
+        let to = square_index(mv.to);
+        let mut undo = Undo {
+            captured: None,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+        let piece = match self.board.piece_on(from) {
+            Some(piece) => piece,
+            None => return undo,
+        };
+
+        // Everything that depends on what the move changes comes out of the hash here and goes
+        // back in below once it's been updated
+        self.hash ^= castling_key(&self.castling) ^ en_passant_key(self) ^ side_to_move_key(self.side_to_move);
+
+        // Take whatever's being captured off first
+        if mv.is_capture() {
+            let captured_square = mv.en_passant_capture_square().map_or(to, square_index);
+            undo.captured = self.board.piece_on(captured_square);
+            if let Some(captured) = undo.captured {
+                self.toggle_piece(captured_square, captured);
+            }
+        }
+
+        self.toggle_piece(from, piece);
+        let placed = match mv.promotion {
+            Some(promotion) => Piece { piece_type: promotion, piece_color: piece.piece_color },
+            None => piece,
+        };
+        self.toggle_piece(to, placed);
+
+        if let Some(rook_move) = mv.castling_rook_move() {
+            let rook = Piece { piece_type: PieceType::Rook, piece_color: piece.piece_color };
+            self.toggle_piece(square_index(rook_move.from), rook);
+            self.toggle_piece(square_index(rook_move.to), rook);
+        }
+
+        revoke_castling_rights(&mut self.castling, from);
+        revoke_castling_rights(&mut self.castling, to);
+
+        if undo.captured.is_some() || piece.piece_type == PieceType::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        // A double pawn push leaves the square it skipped open to en passant for one move
+        self.en_passant = if mv.flags.contains(MoveFlags::DOUBLE_PAWN_PUSH) {
+            Some(((mv.from.0 + mv.to.0) / 2, mv.from.1))
+        } else {
+            None
+        };
+        self.side_to_move = self.side_to_move.opposite();
+        self.hash ^= castling_key(&self.castling) ^ en_passant_key(self) ^ side_to_move_key(self.side_to_move);
+
+        undo
+    }
+
+    // Takes back `mv`, which has to be the last move made with `make_move`
+    pub fn unmake_move(&mut self, mv: Move, undo: Undo) {
+        self.side_to_move = self.side_to_move.opposite();
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+
+        let from = square_index(mv.from);
+        let to = square_index(mv.to);
+        let placed = match self.board.piece_on(to) {
+            Some(piece) => piece,
+            None => return,
+        };
+        // A promoted piece goes back to being a pawn
+        let piece = match mv.promotion {
+            Some(_) => Piece { piece_type: PieceType::Pawn, piece_color: placed.piece_color },
+            None => placed,
+        };
+        self.board.toggle_piece(to, placed);
+        self.board.toggle_piece(from, piece);
+
+        if let Some(rook_move) = mv.castling_rook_move() {
+            let rook = Piece { piece_type: PieceType::Rook, piece_color: piece.piece_color };
+            self.board.toggle_piece(square_index(rook_move.to), rook);
+            self.board.toggle_piece(square_index(rook_move.from), rook);
+        }
+
+        if let Some(captured) = undo.captured {
+            let captured_square = mv.en_passant_capture_square().map_or(to, square_index);
+            self.board.toggle_piece(captured_square, captured);
+        }
+    }
+
+    // Plays `mv` for good and hands back whatever it captured
+    pub fn apply_move(&mut self, mv: Move) -> Option<Piece> {
+        self.make_move(mv).captured
+    }
+
+    // The legal move from `from` to `to` (promoting to `promotion`) with its flags filled in.
+    // Handy for turning clicks or typed squares into a move make_move can play.
+    pub fn find_legal_move(&self, from: (u8, u8), to: (u8, u8), promotion: Option<PieceType>) -> Option<Move> {
+        generate_legal_moves(self)
+            .into_iter()
+            .find(|mv| mv.from == from && mv.to == to && mv.promotion == promotion)
+    }
+}
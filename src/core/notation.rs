@@ -0,0 +1,208 @@
+use std::fmt;
+
+use super::board::*;
+use super::fen::*;
+use super::game_state::*;
+use super::movegen::*;
+use super::moves::*;
+use super::piece::*;
+
+// Reading and writing moves as text. Two notations are supported:
+// - Standard algebraic notation (SAN), what people and PGN files use: "Nbd7", "exd6", "O-O-O", "e8=Q+".
+//   See https://www.chessprogramming.org/Algebraic_Chess_Notation#Standard_Algebraic_Notation_.28SAN.29
+// - Long algebraic notation as the UCI protocol uses it: "e2e4", "e7e8q". Move's Display already
+//   writes this, so only the parsing lives here.
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MoveParseError {
+    // Doesn't look like a move at all
+    InvalidSyntax(String),
+    // Reads fine, but no legal move matches it
+    IllegalMove(String),
+    // More than one legal move matches, e.g. "Nd7" with knights on b8 and f6
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveParseError::InvalidSyntax(text) => write!(f, "'{}' is not a valid move", text),
+            MoveParseError::IllegalMove(text) => write!(f, "'{}' is not a legal move", text),
+            MoveParseError::AmbiguousMove(text) => write!(f, "'{}' could mean more than one move", text),
+        }
+    }
+}
+
+impl std::error::Error for MoveParseError {}
+
+// SAN always uses white's (uppercase) letters, whoever is moving
+fn piece_letter(piece_type: PieceType) -> char {
+    piece_to_char(Piece { piece_type, piece_color: PieceColor::White })
+}
+
+fn piece_type_from_letter(c: char) -> Option<PieceType> {
+    if c.is_ascii_uppercase() {
+        piece_from_char(c).map(|piece| piece.piece_type)
+    } else {
+        None
+    }
+}
+
+impl GameState {
+    // `mv` in SAN, including the check or mate suffix. `mv` has to be legal in this position.
+    pub fn move_to_san(&self, mv: Move) -> String {
+        let mut san = if mv.is_castle() {
+            if mv.to.1 > mv.from.1 { "O-O" } else { "O-O-O" }.to_string()
+        } else {
+            self.move_to_san_without_suffix(mv)
+        };
+
+        let mut after = *self;
+        after.make_move(mv);
+        if is_in_check(&after) {
+            san.push(if generate_legal_moves(&after).is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+
+    fn move_to_san_without_suffix(&self, mv: Move) -> String {
+        let piece_type = self.board.piece_at(mv.from).map_or(PieceType::Pawn, |piece| piece.piece_type);
+        let mut san = String::new();
+
+        if piece_type == PieceType::Pawn {
+            // Pawn captures are named by the file the pawn comes from
+            if mv.is_capture() {
+                san.push((b'a' + mv.from.1) as char);
+                san.push('x');
+            }
+            san.push_str(&position_to_algebraic(mv.to));
+            if let Some(promotion) = mv.promotion {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+            return san;
+        }
+
+        san.push(piece_letter(piece_type));
+        // If another piece of the same type can get to the same square, say which one this is.
+        // The file is enough unless they share it, then the rank, and failing both, the whole square.
+        let rivals: Vec<Move> = generate_legal_moves(self)
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && self.board.piece_at(other.from).map(|piece| piece.piece_type) == Some(piece_type)
+            })
+            .collect();
+        if !rivals.is_empty() {
+            let square = position_to_algebraic(mv.from);
+            if rivals.iter().all(|other| other.from.1 != mv.from.1) {
+                san.push_str(&square[..1]);
+            } else if rivals.iter().all(|other| other.from.0 != mv.from.0) {
+                san.push_str(&square[1..]);
+            } else {
+                san.push_str(&square);
+            }
+        }
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&position_to_algebraic(mv.to));
+        san
+    }
+
+    // Finds the legal move a SAN string describes. It's lenient about the things people tend to
+    // leave off or add: the capture 'x', check marks, "!?" style annotations, the "e.p." after
+    // en passant, the '=' before a promotion, and zeroes instead of O's when castling.
+    pub fn parse_san(&self, text: &str) -> Result<Move, MoveParseError> {
+        let invalid = || MoveParseError::InvalidSyntax(text.to_string());
+
+        let mut san = text.trim();
+        san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+        san = san.trim_end_matches(['+', '#', '!', '?']);
+
+        let legal_moves = generate_legal_moves(self);
+        let found = |candidates: Vec<Move>| match candidates.len() {
+            0 => Err(MoveParseError::IllegalMove(text.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(MoveParseError::AmbiguousMove(text.to_string())),
+        };
+
+        let castle_file = match san {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(file) = castle_file {
+            return found(legal_moves.into_iter().filter(|mv| mv.is_castle() && mv.to.1 == file).collect());
+        }
+
+        // Promotion comes last, with or without the '='
+        let mut promotion = None;
+        if let Some(last) = san.chars().last().and_then(piece_type_from_letter) {
+            promotion = Some(last);
+            san = &san[..san.len() - 1];
+            san = san.strip_suffix('=').unwrap_or(san);
+        }
+
+        // Then the piece letter, which is left off for pawns
+        let piece_type = san.chars().next().and_then(piece_type_from_letter);
+        if piece_type.is_some() {
+            san = &san[1..];
+        }
+        let piece_type = piece_type.unwrap_or(PieceType::Pawn);
+
+        // What's left is the destination square, with maybe a file and/or rank before it saying
+        // where the piece comes from. Some people write "Ng1-f3" so dashes are dropped too.
+        let squares: String = san.chars().filter(|c| *c != 'x' && *c != '-').collect();
+        if squares.len() < 2 || !squares.is_ascii() {
+            return Err(invalid());
+        }
+        let (from_hint, to) = squares.split_at(squares.len() - 2);
+        let to = algebraic_to_position(to).ok_or_else(invalid)?;
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in from_hint.chars() {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return Err(invalid()),
+            }
+        }
+
+        found(
+            legal_moves
+                .into_iter()
+                .filter(|mv| {
+                    mv.to == to
+                        && mv.promotion == promotion
+                        && !mv.is_castle()
+                        && from_file.is_none_or(|file| mv.from.1 == file)
+                        && from_rank.is_none_or(|rank| mv.from.0 == rank)
+                        && self.board.piece_at(mv.from).map(|piece| piece.piece_type) == Some(piece_type)
+                })
+                .collect(),
+        )
+    }
+
+    // Finds the legal move for a UCI style "e2e4" or "e7e8q"
+    pub fn parse_uci_move(&self, text: &str) -> Result<Move, MoveParseError> {
+        let invalid = || MoveParseError::InvalidSyntax(text.to_string());
+        if !text.is_ascii() || !(4..=5).contains(&text.len()) {
+            return Err(invalid());
+        }
+
+        let from = algebraic_to_position(&text[0..2]).ok_or_else(invalid)?;
+        let to = algebraic_to_position(&text[2..4]).ok_or_else(invalid)?;
+        let promotion = match text[4..].chars().next() {
+            Some(c) => match piece_from_char(c).map(|piece| piece.piece_type) {
+                Some(piece_type) if PROMOTION_PIECES.contains(&piece_type) => Some(piece_type),
+                _ => return Err(invalid()),
+            },
+            None => None,
+        };
+
+        self.find_legal_move(from, to, promotion)
+            .ok_or_else(|| MoveParseError::IllegalMove(text.to_string()))
+    }
+}
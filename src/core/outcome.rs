@@ -0,0 +1,118 @@
+use std::fmt;
+
+use super::bitboard::*;
+use super::board::*;
+use super::game_state::*;
+use super::movegen::*;
+use super::piece::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawReason {
+    // The same position for the third time, with the same side to move and the same moves available
+    ThreefoldRepetition,
+    // The fifth time, at which point it's drawn whether or not anyone claims it
+    FivefoldRepetition,
+    // 50 moves by each side without a capture or a pawn move
+    FiftyMoveRule,
+    // Same again for 75 moves, also drawn without a claim
+    SeventyFiveMoveRule,
+    // Neither side has enough left to ever checkmate
+    InsufficientMaterial,
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::FivefoldRepetition => write!(f, "fivefold repetition"),
+            DrawReason::FiftyMoveRule => write!(f, "fifty-move rule"),
+            DrawReason::SeventyFiveMoveRule => write!(f, "seventy-five-move rule"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameOutcome {
+    // The color that delivered mate, i.e. the winner
+    Checkmate(PieceColor),
+    Stalemate,
+    Draw(DrawReason),
+}
+
+impl fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameOutcome::Checkmate(winner) => write!(f, "Checkmate, {} wins", winner),
+            GameOutcome::Stalemate => write!(f, "Stalemate"),
+            GameOutcome::Draw(reason) => write!(f, "Draw by {}", reason),
+        }
+    }
+}
+
+// How many times the current position has come up, counting this time. `history` holds the
+// hashes of the positions before it, oldest first.
+pub fn repetition_count(state: &GameState, history: &[u64]) -> usize {
+    // Nothing from before the last capture or pawn move can come back, so there's no need to look further
+    let reversible = (state.halfmove_clock as usize).min(history.len());
+    1 + history[history.len() - reversible..]
+        .iter()
+        .filter(|hash| **hash == state.hash)
+        .count()
+}
+
+// Positions where no sequence of moves can end in mate: bare kings, a king and one minor piece
+// against a bare king, and bishops that all run on the same color of square
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let kings = board.pieces[PieceType::King.index()];
+    let knights = board.pieces[PieceType::Knight.index()];
+    let bishops = board.pieces[PieceType::Bishop.index()];
+    let others = board.occupied() & !kings & !knights & !bishops;
+    if others != EMPTY {
+        return false;
+    }
+
+    let minors = knights | bishops;
+    if minors.count_ones() <= 1 {
+        return true;
+    }
+    // Any number of bishops on one color can't cover the other, and it takes both to mate
+    knights == EMPTY && (bishops & LIGHT_SQUARES == EMPTY || bishops & !LIGHT_SQUARES == EMPTY)
+}
+
+// How the game stands after the last move, or None if it's still going. `history` holds the
+// hashes of every position before this one, for spotting repetitions.
+//
+// The threefold and fifty-move draws would normally have to be claimed, but there's nobody to
+// claim them here, so they end the game just like the fivefold and seventy-five-move ones.
+pub fn game_outcome(state: &GameState, history: &[u64]) -> Option<GameOutcome> {
+    if generate_legal_moves(state).is_empty() {
+        return Some(if is_in_check(state) {
+            GameOutcome::Checkmate(state.side_to_move.opposite())
+        } else {
+            GameOutcome::Stalemate
+        });
+    }
+
+    if is_insufficient_material(&state.board) {
+        return Some(GameOutcome::Draw(DrawReason::InsufficientMaterial));
+    }
+
+    // Reaching these with the checkmating move still counts, which is why they come after it.
+    // The automatic draws come first so a game that ran past a claim reports the right one.
+    let repetitions = repetition_count(state, history);
+    if repetitions >= 5 {
+        return Some(GameOutcome::Draw(DrawReason::FivefoldRepetition));
+    }
+    if state.halfmove_clock >= 150 {
+        return Some(GameOutcome::Draw(DrawReason::SeventyFiveMoveRule));
+    }
+    if repetitions >= 3 {
+        return Some(GameOutcome::Draw(DrawReason::ThreefoldRepetition));
+    }
+    if state.halfmove_clock >= 100 {
+        return Some(GameOutcome::Draw(DrawReason::FiftyMoveRule));
+    }
+
+    None
+}
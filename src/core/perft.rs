@@ -0,0 +1,43 @@
+use super::game_state::*;
+use super::movegen::*;
+use super::moves::*;
+
+// Counts the leaf nodes of the legal move tree `depth` plies deep. Comparing the counts with
+// published ones is the standard way to shake out move generation bugs.
+// See https://www.chessprogramming.org/Perft_Results
+pub fn perft(state: &mut GameState, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = generate_legal_moves(state);
+    // The last ply doesn't need playing out, the moves are already legal
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mv in moves {
+        let undo = state.make_move(mv);
+        nodes += perft(state, depth - 1);
+        state.unmake_move(mv, undo);
+    }
+    nodes
+}
+
+// Perft split up by the first move, which narrows a wrong count down to the move causing it
+pub fn perft_divide(state: &mut GameState, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    generate_legal_moves(state)
+        .into_iter()
+        .map(|mv| {
+            let undo = state.make_move(mv);
+            let nodes = perft(state, depth - 1);
+            state.unmake_move(mv, undo);
+            (mv, nodes)
+        })
+        .collect()
+}
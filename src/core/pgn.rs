@@ -0,0 +1,561 @@
+use std::{
+    fmt,
+    iter::Peekable,
+    mem,
+    str::Chars,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+    vec,
+};
+
+use super::fen::*;
+use super::game_state::*;
+use super::moves::*;
+use super::notation::*;
+use super::outcome::*;
+use super::piece::*;
+
+// Reading and writing games in Portable Game Notation.
+// See https://www.chessprogramming.org/Portable_Game_Notation for the format.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    // Still going, abandoned, or just not known. Written as "*".
+    Unknown,
+}
+
+impl GameResult {
+    pub fn from_outcome(outcome: GameOutcome) -> GameResult {
+        match outcome {
+            GameOutcome::Checkmate(PieceColor::White) => GameResult::WhiteWins,
+            GameOutcome::Checkmate(PieceColor::Black) => GameResult::BlackWins,
+            GameOutcome::Stalemate | GameOutcome::Draw(_) => GameResult::Draw,
+        }
+    }
+
+    fn from_pgn(text: &str) -> Option<GameResult> {
+        match text {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let result = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        };
+        write!(f, "{}", result)
+    }
+}
+
+// One move of a game along with everything written around it
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnMove {
+    pub mv: Move,
+    // The move in SAN, tidied up the way move_to_san writes it
+    pub san: String,
+    // Numeric annotation glyphs, e.g. $1 for a good move. "!" and "?" style suffixes end up here too.
+    pub nags: Vec<u8>,
+    // Comments before the move. Only the first move of a game or variation has these.
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    // Clock readings from "[%clk 1:05:00]" and "[%emt 0:00:12]" commands in the comments: the time the
+    // player had left after the move, and the time they spent on it
+    pub clock: Option<Duration>,
+    pub elapsed: Option<Duration>,
+    // Other lines that could have been played instead of this move, each starting from the
+    // same position this move was played in
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnGame {
+    // Tag pairs in the order they appeared, e.g. ("White", "Carlsen, Magnus")
+    pub tags: Vec<(String, String)>,
+    // The starting position, from the FEN tag if there is one
+    pub start: GameState,
+    pub moves: Vec<PgnMove>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Just the moves actually played, without the variations
+    pub fn mainline(&self) -> Vec<Move> {
+        self.moves.iter().map(|pgn_move| pgn_move.mv).collect()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PgnError {
+    // Something that can't start any PGN token
+    InvalidCharacter(char),
+    // A tag pair that isn't [Name "value"]
+    InvalidTag(String),
+    // A '{' comment with no closing '}'
+    UnterminatedComment,
+    // A '(' with no matching ')'
+    UnterminatedVariation,
+    // Something that's out of place where it is, like a ')' with no '(' before it
+    UnexpectedToken(String),
+    InvalidFen(FenError),
+    // A move that can't be played. `move_number` is the fullmove number it was played on.
+    InvalidMove { move_number: u32, error: MoveParseError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::InvalidCharacter(character) => write!(f, "unexpected character '{}'", character),
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair '{}'", tag),
+            PgnError::UnterminatedComment => write!(f, "comment is missing its closing '}}'"),
+            PgnError::UnterminatedVariation => write!(f, "variation is missing its closing ')'"),
+            PgnError::UnexpectedToken(token) => write!(f, "unexpected '{}'", token),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::InvalidMove { move_number, error } => write!(f, "move {}: {}", move_number, error),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Tag(String, String),
+    // A move, or anything else made of symbol characters
+    Symbol(String),
+    Nag(u8),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+    Result(GameResult),
+}
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)
+}
+
+fn take_while(chars: &mut Peekable<Chars>, keep: impl Fn(char) -> bool) -> String {
+    let mut taken = String::new();
+    while let Some(c) = chars.peek().copied().filter(|c| keep(*c)) {
+        taken.push(c);
+        chars.next();
+    }
+    taken
+}
+
+// The inside of a tag pair: a name, then a quoted value that can have \" and \\ in it
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (name, value) = tag.trim().split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(if c == '\\' { chars.next()? } else { c });
+    }
+    Some((name.to_string(), unescaped))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(&c) = chars.peek() {
+        // A '%' at the start of a line hides the rest of it
+        if line_start && c == '%' {
+            take_while(&mut chars, |c| c != '\n');
+            continue;
+        }
+        line_start = c == '\n';
+
+        if c.is_whitespace() || c == '.' {
+            // Dots only ever follow move numbers, which don't matter
+            chars.next();
+        } else if c == '[' {
+            chars.next();
+            // Tag values can have ']' in their quotes, so track whether we're inside one
+            let mut tag = String::new();
+            let mut in_quotes = false;
+            let mut escaped = false;
+            loop {
+                let c = chars.next().ok_or_else(|| PgnError::InvalidTag(tag.clone()))?;
+                if c == ']' && !in_quotes {
+                    break;
+                }
+                if c == '"' && !escaped {
+                    in_quotes = !in_quotes;
+                }
+                escaped = c == '\\' && !escaped;
+                tag.push(c);
+            }
+            let (name, value) = parse_tag(&tag).ok_or(PgnError::InvalidTag(tag))?;
+            tokens.push(Token::Tag(name, value));
+        } else if c == '{' {
+            chars.next();
+            let comment = take_while(&mut chars, |c| c != '}');
+            chars.next().ok_or(PgnError::UnterminatedComment)?;
+            tokens.push(Token::Comment(comment.trim().to_string()));
+        } else if c == ';' {
+            chars.next();
+            let comment = take_while(&mut chars, |c| c != '\n');
+            tokens.push(Token::Comment(comment.trim().to_string()));
+        } else if c == '(' {
+            chars.next();
+            tokens.push(Token::VariationStart);
+        } else if c == ')' {
+            chars.next();
+            tokens.push(Token::VariationEnd);
+        } else if c == '*' {
+            chars.next();
+            tokens.push(Token::Result(GameResult::Unknown));
+        } else if c == '$' {
+            chars.next();
+            let nag = take_while(&mut chars, |c| c.is_ascii_digit());
+            tokens.push(Token::Nag(nag.parse().map_err(|_| PgnError::UnexpectedToken(format!("${}", nag)))?));
+        } else if c == '!' || c == '?' {
+            let suffix = take_while(&mut chars, |c| c == '!' || c == '?');
+            let nag = match suffix.as_str() {
+                "!" => 1,
+                "?" => 2,
+                "!!" => 3,
+                "??" => 4,
+                "!?" => 5,
+                "?!" => 6,
+                _ => return Err(PgnError::UnexpectedToken(suffix)),
+            };
+            tokens.push(Token::Nag(nag));
+        } else if is_symbol_char(c) {
+            let symbol = take_while(&mut chars, is_symbol_char);
+            // "e.p." after an en passant capture says nothing the move doesn't already
+            if symbol == "e" && chars.clone().take(3).collect::<String>() == ".p." {
+                chars.nth(2);
+            } else if let Some(result) = GameResult::from_pgn(&symbol) {
+                tokens.push(Token::Result(result));
+            } else if !symbol.chars().all(|c| c.is_ascii_digit()) {
+                // All digits is a move number, which can be skipped
+                tokens.push(Token::Symbol(symbol));
+            }
+        } else {
+            return Err(PgnError::InvalidCharacter(c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// "1:05:00" or "0:00:04.5" style durations, as clock commands use
+fn parse_clock(text: &str) -> Option<Duration> {
+    let mut parts = text.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+fn format_clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let mut clock = format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    // Tenths only when there are any, which is usually just when time is short
+    let tenths = duration.subsec_millis() / 100;
+    if tenths > 0 {
+        clock.push_str(&format!(".{}", tenths));
+    }
+    clock
+}
+
+// Pulls a "[%name value]" command out of `comment` and reads its value as a clock time
+fn take_clock_command(comment: &mut String, name: &str) -> Option<Duration> {
+    let prefix = format!("[%{} ", name);
+    let start = comment.find(&prefix)?;
+    let end = start + comment[start..].find(']')?;
+    let clock = parse_clock(comment[start + prefix.len()..end].trim());
+    if clock.is_some() {
+        comment.replace_range(start..=end, "");
+        *comment = comment.trim().to_string();
+    }
+    clock
+}
+
+// Reads moves played from `state` until the end of the line: the ')' closing a variation, or
+// for the main line the result, the next game's tags or the end of the file
+fn parse_line(
+    tokens: &mut Peekable<vec::IntoIter<Token>>,
+    mut state: GameState,
+    in_variation: bool,
+) -> Result<(Vec<PgnMove>, Option<GameResult>), PgnError> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    // The position before the last move, which is where its variations start from
+    let mut previous = state;
+    let mut comments_before = Vec::new();
+
+    loop {
+        match tokens.peek() {
+            None if in_variation => return Err(PgnError::UnterminatedVariation),
+            None => return Ok((moves, None)),
+            // A new game started without this one giving a result
+            Some(Token::Tag(..)) if in_variation => return Err(PgnError::UnterminatedVariation),
+            Some(Token::Tag(..)) => return Ok((moves, None)),
+            _ => {}
+        }
+
+        match tokens.next().unwrap() {
+            Token::Symbol(san) => {
+                let mv = state.parse_san(&san).map_err(|error| PgnError::InvalidMove {
+                    move_number: state.fullmove_number,
+                    error,
+                })?;
+                moves.push(PgnMove {
+                    mv,
+                    san: state.move_to_san(mv),
+                    nags: Vec::new(),
+                    comments_before: mem::take(&mut comments_before),
+                    comments: Vec::new(),
+                    clock: None,
+                    elapsed: None,
+                    variations: Vec::new(),
+                });
+                previous = state;
+                state.make_move(mv);
+            }
+            Token::Nag(nag) => {
+                if let Some(last) = moves.last_mut() {
+                    last.nags.push(nag);
+                }
+            }
+            Token::Comment(mut comment) => match moves.last_mut() {
+                Some(last) => {
+                    last.clock = take_clock_command(&mut comment, "clk").or(last.clock);
+                    last.elapsed = take_clock_command(&mut comment, "emt").or(last.elapsed);
+                    // Don't keep comments that were only there for the clock
+                    if !comment.is_empty() {
+                        last.comments.push(comment);
+                    }
+                }
+                None => comments_before.push(comment),
+            },
+            Token::VariationStart => {
+                let last = moves.last_mut().ok_or_else(|| PgnError::UnexpectedToken("(".to_string()))?;
+                let (variation, _) = parse_line(tokens, previous, true)?;
+                last.variations.push(variation);
+            }
+            Token::VariationEnd if in_variation => return Ok((moves, None)),
+            Token::VariationEnd => return Err(PgnError::UnexpectedToken(")".to_string())),
+            Token::Result(result) if in_variation => return Err(PgnError::UnexpectedToken(result.to_string())),
+            Token::Result(result) => return Ok((moves, Some(result))),
+            Token::Tag(..) => unreachable!(),
+        }
+    }
+}
+
+fn parse_game(tokens: &mut Peekable<vec::IntoIter<Token>>) -> Result<PgnGame, PgnError> {
+    let mut tags = Vec::new();
+    while let Some(Token::Tag(..)) = tokens.peek() {
+        if let Some(Token::Tag(name, value)) = tokens.next() {
+            tags.push((name, value));
+        }
+    }
+
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => parse_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => parse_fen(STARTING_BOARD_FEN).map_err(PgnError::InvalidFen)?,
+    };
+
+    let (moves, result) = parse_line(tokens, start, false)?;
+    // Fall back on the Result tag if the movetext didn't end with one
+    let result = result
+        .or_else(|| tags.iter().find(|(name, _)| name == "Result").and_then(|(_, result)| GameResult::from_pgn(result)))
+        .unwrap_or(GameResult::Unknown);
+
+    Ok(PgnGame { tags, start, moves, result })
+}
+
+// Every game in `text`, in order. Every move is checked, so a game that comes back is one
+// that can be played through.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    // Files saved on Windows often start with a byte order mark
+    let mut tokens = tokenize(text.trim_start_matches('\u{feff}'))?.into_iter().peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        let game = parse_game(&mut tokens)?;
+        // Stray comments between games aren't a game of their own
+        if !game.tags.is_empty() || !game.moves.is_empty() {
+            games.push(game);
+        }
+    }
+    Ok(games)
+}
+
+// Names of the Seven Tag Roster, which every exported game has and always in this order
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// `time` as a PGN date tag, e.g. "2021.03.14"
+pub fn pgn_date(time: SystemTime) -> String {
+    let days = time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() / 86400) as i64;
+    // Days since 1970 to a calendar date, from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl PgnGame {
+    // A game of `moves` played from `start`, with the Seven Tag Roster all unknown. Games that
+    // don't start from the usual position get the SetUp and FEN tags they need.
+    pub fn new(start: GameState, moves: &[Move]) -> PgnGame {
+        let mut tags: Vec<(String, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|name| (name.to_string(), "?".to_string()))
+            .collect();
+        tags[2].1 = "????.??.??".to_string();
+        tags[6].1 = GameResult::Unknown.to_string();
+        if parse_fen(STARTING_BOARD_FEN) != Ok(start) {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), start.to_fen()));
+        }
+
+        let mut state = start;
+        let moves = moves
+            .iter()
+            .map(|mv| {
+                let san = state.move_to_san(*mv);
+                state.make_move(*mv);
+                PgnMove {
+                    mv: *mv,
+                    san,
+                    nags: Vec::new(),
+                    comments_before: Vec::new(),
+                    comments: Vec::new(),
+                    clock: None,
+                    elapsed: None,
+                    variations: Vec::new(),
+                }
+            })
+            .collect();
+
+        PgnGame { tags, start, moves, result: GameResult::Unknown }
+    }
+
+    // Replaces the tag called `name`, or adds it at the end if there isn't one
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+}
+
+// Turns `moves` played from `state` into movetext tokens, variations and all
+fn write_line(moves: &[PgnMove], mut state: GameState, tokens: &mut Vec<String>) {
+    // Black moves only get a number at the start of a line or after something that interrupts it
+    let mut needs_number = true;
+
+    for pgn_move in moves {
+        for comment in pgn_move.comments_before.iter() {
+            tokens.push(format!("{{{}}}", comment.replace('}', "")));
+        }
+        match state.side_to_move {
+            PieceColor::White => tokens.push(format!("{}.", state.fullmove_number)),
+            PieceColor::Black if needs_number || !pgn_move.comments_before.is_empty() => {
+                tokens.push(format!("{}...", state.fullmove_number))
+            }
+            PieceColor::Black => {}
+        }
+        tokens.push(pgn_move.san.clone());
+        tokens.extend(pgn_move.nags.iter().map(|nag| format!("${}", nag)));
+
+        let mut commands = Vec::new();
+        if let Some(clock) = pgn_move.clock {
+            commands.push(format!("[%clk {}]", format_clock(clock)));
+        }
+        if let Some(elapsed) = pgn_move.elapsed {
+            commands.push(format!("[%emt {}]", format_clock(elapsed)));
+        }
+        if !commands.is_empty() {
+            tokens.push(format!("{{{}}}", commands.join(" ")));
+        }
+        for comment in pgn_move.comments.iter() {
+            tokens.push(format!("{{{}}}", comment.replace('}', "")));
+        }
+
+        for variation in pgn_move.variations.iter() {
+            let mut variation_tokens = Vec::new();
+            write_line(variation, state, &mut variation_tokens);
+            // The brackets go right up against the first and last moves, as in "(1... c5)"
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+                variation_tokens[0].insert(0, '(');
+                tokens.extend(variation_tokens);
+            }
+        }
+
+        needs_number = !commands.is_empty() || !pgn_move.comments.is_empty() || !pgn_move.variations.is_empty();
+        state.make_move(pgn_move.mv);
+    }
+}
+
+impl fmt::Display for PgnGame {
+    // Export format: the Seven Tag Roster first, then any other tags, a blank line, and the
+    // movetext wrapped at 80 characters
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in SEVEN_TAG_ROSTER.iter() {
+            let value = match *name {
+                // The result in the tags always matches the one at the end of the moves
+                "Result" => self.result.to_string(),
+                _ => self.tag(name).unwrap_or("?").to_string(),
+            };
+            writeln!(f, "[{} \"{}\"]", name, escape_tag_value(&value))?;
+        }
+        for (name, value) in self.tags.iter().filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str())) {
+            writeln!(f, "[{} \"{}\"]", name, escape_tag_value(value))?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        write_line(&self.moves, self.start, &mut tokens);
+        tokens.push(self.result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
@@ -0,0 +1,481 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::evaluation::*;
+use super::game_state::*;
+use super::movegen::*;
+use super::moves::*;
+use super::outcome::*;
+use super::piece::*;
+
+// Negamax alpha-beta with iterative deepening, a quiescence search on the leaves and a
+// transposition table. Moves are tried hash move first, then captures biggest victim first,
+// then the quiet moves that caused cutoffs at the same depth elsewhere ("killers").
+// See https://www.chessprogramming.org/Alpha-Beta and https://www.chessprogramming.org/Iterative_Deepening
+
+// Being mated right now. Mates further off score a ply less for every ply to them, so the
+// search goes for the quickest mate and puts off getting mated for as long as it can.
+pub const MATE_SCORE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+// How deep the search can go, counting extensions and the quiescence search
+const MAX_PLY: usize = 128;
+// Scores past this are mates
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+// How many nodes go by between looks at the clock
+const CHECK_EVERY: u64 = 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Score {
+    Centipawns(i32),
+    // Mate in this many moves, negative when it's the side to move getting mated
+    Mate(i32),
+}
+
+impl Score {
+    fn from_search(score: i32) -> Score {
+        if score >= MATE_BOUND {
+            Score::Mate((MATE_SCORE - score + 1) / 2)
+        } else if score <= -MATE_BOUND {
+            Score::Mate(-(MATE_SCORE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+// The way UCI writes it: "cp 35" or "mate -2"
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "cp {}", cp),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+// When to stop searching. Whichever is hit first wins, and with none set the search runs
+// until it's out of depth or told to stop. At least one full ply is always searched so
+// there's a move to play.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    // Setting this from another thread stops the search early
+    pub stop: Option<Arc<AtomicBool>>,
+    // How many of the best moves to find lines for. Anything under 2 means just the best one.
+    pub multi_pv: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SearchResult {
+    // None when there are no legal moves
+    pub best_move: Option<Move>,
+    pub score: Score,
+    // The line the search expects, starting with the best move
+    pub pv: Vec<Move>,
+    // The next best moves' scores and lines, best first, when more than one line was asked for
+    pub alternatives: Vec<(Score, Vec<Move>)>,
+    // How many plies deep the last finished iteration went
+    pub depth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    // The score is at least this much, the search cut off before finding out how much more
+    Lower,
+    // The score is at most this much, nothing beat alpha
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    hash: u64,
+    best_move: Option<Move>,
+    score: i32,
+    depth: i32,
+    bound: Bound,
+}
+
+// Positions already searched, so ones reached again by a different move order don't have to
+// be searched from scratch, and so the last iteration's best moves get tried first
+struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    fn new(megabytes: usize) -> Self {
+        // A power of two so the hash can be masked down to an index
+        let wanted = (megabytes * 1024 * 1024 / std::mem::size_of::<Option<Entry>>()).max(1);
+        let size = if wanted.is_power_of_two() { wanted } else { wanted.next_power_of_two() / 2 };
+        TranspositionTable { entries: vec![None; size] }
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash & (self.entries.len() as u64 - 1)) as usize
+    }
+
+    fn probe(&self, hash: u64) -> Option<Entry> {
+        self.entries[self.index(hash)].filter(|entry| entry.hash == hash)
+    }
+
+    // Deeper searches of the same position are worth more, anything else just replaces what's there
+    fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.hash);
+        if self.entries[index].is_none_or(|old| old.hash != entry.hash || old.depth <= entry.depth) {
+            self.entries[index] = Some(entry);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+}
+
+// Mate scores count plies from the root, but the table is shared between positions at different
+// plies, so they're stored counting from the position itself instead
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// A search engine. The transposition table is kept between searches, so searching the moves of
+// one game in order gets a head start from the ones before.
+pub struct Search {
+    table: TranspositionTable,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Search::new(16)
+    }
+}
+
+impl Search {
+    // A search with a `hash_megabytes` transposition table
+    pub fn new(hash_megabytes: usize) -> Self {
+        Search { table: TranspositionTable::new(hash_megabytes) }
+    }
+
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.table = TranspositionTable::new(megabytes);
+    }
+
+    // Forget everything from earlier searches, for starting a new game
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    // Looks for the best move in `state`. `history` holds the hashes of the positions before it,
+    // oldest first, so the search can see repetitions coming. `on_iteration` gets the result of
+    // every finished iteration as it comes in.
+    pub fn search(
+        &mut self,
+        state: &GameState,
+        history: &[u64],
+        limits: &SearchLimits,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let started = Instant::now();
+        let mut searcher = Searcher {
+            table: &mut self.table,
+            limits,
+            started,
+            nodes: 0,
+            aborted: false,
+            can_abort: false,
+            positions: history.to_vec(),
+            killers: [[None; 2]; MAX_PLY],
+            excluded: Vec::new(),
+        };
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: Score::Centipawns(0),
+            pv: Vec::new(),
+            alternatives: Vec::new(),
+            depth: 0,
+            nodes: 0,
+            time: Duration::from_secs(0),
+        };
+        let legal_moves = generate_legal_moves(state).len();
+        if legal_moves == 0 {
+            if is_in_check(state) {
+                result.score = Score::Mate(0);
+            }
+            return result;
+        }
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32 / 2).clamp(1, MAX_PLY as u32 / 2);
+        let lines_wanted = limits.multi_pv.clamp(1, legal_moves);
+        let mut root = *state;
+        for depth in 1..=max_depth {
+            // Each line after the first is the best the root can do without the moves already found
+            let mut lines = Vec::new();
+            searcher.excluded.clear();
+            while lines.len() < lines_wanted {
+                let mut pv = Vec::new();
+                let score = searcher.negamax(&mut root, depth as i32, 0, -INFINITY, INFINITY, &mut pv);
+                if searcher.aborted {
+                    break;
+                }
+                searcher.excluded.push(pv[0]);
+                lines.push((score, pv));
+            }
+            if searcher.aborted {
+                break;
+            }
+            searcher.can_abort = true;
+
+            let (score, pv) = lines.remove(0);
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score: Score::from_search(score),
+                pv,
+                alternatives: lines.into_iter().map(|(score, pv)| (Score::from_search(score), pv)).collect(),
+                depth,
+                nodes: searcher.nodes,
+                time: started.elapsed(),
+            };
+            on_iteration(&result);
+
+            // A mate that's been seen all the way to the end won't get any better
+            if score.abs() >= MATE_BOUND && MATE_SCORE - score.abs() <= depth as i32 {
+                break;
+            }
+            // Each iteration takes a few times as long as the last, so if half the time is gone
+            // the next one won't finish
+            if limits.time.is_some_and(|time| started.elapsed() * 2 > time) {
+                break;
+            }
+        }
+        result.nodes = searcher.nodes;
+        result.time = started.elapsed();
+        result
+    }
+}
+
+// Everything that only lasts as long as one search
+struct Searcher<'a> {
+    table: &'a mut TranspositionTable,
+    limits: &'a SearchLimits,
+    started: Instant,
+    nodes: u64,
+    // Set once a limit has been hit. Everything after that unwinds without a usable score.
+    aborted: bool,
+    // The first iteration always finishes so there's a move to play
+    can_abort: bool,
+    // The hashes of every position from the start of the game to the current one, not counting it
+    positions: Vec<u64>,
+    // Two quiet moves per ply that caused a cutoff
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    // Root moves to leave out, the ones already given their own line
+    excluded: Vec<Move>,
+}
+
+impl Searcher<'_> {
+    fn out_of_time(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        if !self.can_abort {
+            return false;
+        }
+        let limits = self.limits;
+        self.aborted = limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || limits.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+            // The clock is slow to read compared to a node, so it's only looked at now and then
+            || (self.nodes.is_multiple_of(CHECK_EVERY) && limits.time.is_some_and(|time| self.started.elapsed() >= time));
+        self.aborted
+    }
+
+    fn is_draw(&self, state: &GameState) -> bool {
+        // A position coming back once is enough, if it was worth going back to the first time
+        // then it will be again and the game will be drawn
+        state.halfmove_clock >= 100
+            || repetition_count(state, &self.positions) >= 2
+            || is_insufficient_material(&state.board)
+    }
+
+    fn negamax(&mut self, state: &mut GameState, depth: i32, ply: usize, mut alpha: i32, beta: i32, pv: &mut Vec<Move>) -> i32 {
+        pv.clear();
+        if self.out_of_time() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0 && self.is_draw(state) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(state);
+        }
+
+        // Look one ply further when in check, there are few replies and it's often leading somewhere
+        let in_check = is_in_check(state);
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth <= 0 {
+            return self.quiescence(state, ply, alpha, beta);
+        }
+
+        let entry = self.table.probe(state.hash);
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        let mut moves = generate_legal_moves(state);
+        if moves.is_empty() {
+            return if in_check { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+        if ply == 0 {
+            moves.retain(|mv| !self.excluded.contains(mv));
+        }
+        self.order_moves(state, &mut moves, entry.and_then(|entry| entry.best_move), ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut line = Vec::new();
+        for mv in moves {
+            self.positions.push(state.hash);
+            let undo = state.make_move(mv);
+            let score = -self.negamax(state, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            state.unmake_move(mv, undo);
+            self.positions.pop();
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(mv);
+                    pv.extend_from_slice(&line);
+                }
+                if score >= beta {
+                    if !mv.is_capture() && mv.promotion.is_none() && self.killers[ply][0] != Some(mv) {
+                        self.killers[ply][1] = self.killers[ply][0];
+                        self.killers[ply][0] = Some(mv);
+                    }
+                    break;
+                }
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        // With moves left out the root's score isn't really its own
+        if ply > 0 || self.excluded.is_empty() {
+            self.table.store(Entry { hash: state.hash, best_move, score: score_to_table(best_score, ply), depth, bound });
+        }
+        best_score
+    }
+
+    // Plays out the captures at the end of the main search, so it doesn't stop in the middle of
+    // an exchange and think it's come out a piece up
+    fn quiescence(&mut self, state: &mut GameState, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+        self.nodes += 1;
+        if ply >= MAX_PLY - 1 {
+            return evaluate(state);
+        }
+
+        // Unless in check there's always the option of not capturing anything
+        let in_check = is_in_check(state);
+        let mut best_score = -INFINITY;
+        if !in_check {
+            best_score = evaluate(state);
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+        }
+
+        let mut moves = generate_legal_moves(state);
+        if moves.is_empty() && in_check {
+            return -MATE_SCORE + ply as i32;
+        }
+        // In check every way out has to be looked at, otherwise only captures and queening
+        if !in_check {
+            moves.retain(|mv| mv.is_capture() || mv.promotion == Some(PieceType::Queen));
+        }
+        self.order_moves(state, &mut moves, None, ply);
+
+        for mv in moves {
+            let undo = state.make_move(mv);
+            let score = -self.quiescence(state, ply + 1, -beta, -alpha);
+            state.unmake_move(mv, undo);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                alpha = alpha.max(score);
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+        best_score
+    }
+
+    fn order_moves(&self, state: &GameState, moves: &mut [Move], hash_move: Option<Move>, ply: usize) {
+        moves.sort_by_cached_key(|mv| {
+            Reverse(if Some(*mv) == hash_move {
+                1_000_000
+            } else if mv.is_capture() {
+                // Most valuable victim, least valuable attacker
+                let victim = state.board.piece_at(mv.en_passant_capture_square().unwrap_or(mv.to));
+                let attacker = state.board.piece_at(mv.from);
+                100_000 + victim.map_or(0, |piece| piece_value(piece.piece_type)) * 10
+                    - attacker.map_or(0, |piece| piece_value(piece.piece_type))
+            } else if let Some(promotion) = mv.promotion {
+                90_000 + piece_value(promotion)
+            } else if self.killers[ply][0] == Some(*mv) {
+                80_000
+            } else if self.killers[ply][1] == Some(*mv) {
+                79_000
+            } else {
+                0
+            })
+        });
+    }
+}
@@ -0,0 +1,125 @@
+use super::attacks::*;
+use super::bitboard::*;
+use super::game_state::*;
+use super::piece::*;
+
+// Zobrist hashing: every (piece, square) pair, the side to move, each castling right and each
+// en passant file gets a random number, and a position's hash is all of its numbers XORed
+// together. Making a move then only has to XOR in and out the handful of things that changed.
+// See https://www.chessprogramming.org/Zobrist_Hashing
+
+// splitmix64, which is good enough for keys and simple enough to run at compile time
+const fn next_key(seed: u64) -> (u64, u64) {
+    let seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (seed, z ^ (z >> 31))
+}
+
+struct Keys {
+    // Indexed by PieceColor::index, then PieceType::index, then square
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    // White kingside, white queenside, black kingside, black queenside
+    castling: [u64; 4],
+    en_passant_files: [u64; 8],
+}
+
+const fn keys_table() -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        black_to_move: 0,
+        castling: [0; 4],
+        en_passant_files: [0; 8],
+    };
+    let mut seed = 0x2545_f491_4f6c_dd1d;
+    let mut key;
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece_type = 0;
+        while piece_type < 6 {
+            let mut square = 0;
+            while square < 64 {
+                (seed, key) = next_key(seed);
+                keys.pieces[color][piece_type][square] = key;
+                square += 1;
+            }
+            piece_type += 1;
+        }
+        color += 1;
+    }
+    (seed, key) = next_key(seed);
+    keys.black_to_move = key;
+    let mut i = 0;
+    while i < 4 {
+        (seed, key) = next_key(seed);
+        keys.castling[i] = key;
+        i += 1;
+    }
+    let mut file = 0;
+    while file < 8 {
+        (seed, key) = next_key(seed);
+        keys.en_passant_files[file] = key;
+        file += 1;
+    }
+    keys
+}
+
+const KEYS: Keys = keys_table();
+
+pub fn piece_key(piece: Piece, square: u8) -> u64 {
+    KEYS.pieces[piece.piece_color.index()][piece.piece_type.index()][square as usize]
+}
+
+pub fn side_to_move_key(color: PieceColor) -> u64 {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => KEYS.black_to_move,
+    }
+}
+
+pub fn castling_key(castling: &CastlingRights) -> u64 {
+    let rights = [
+        castling.white_kingside,
+        castling.white_queenside,
+        castling.black_kingside,
+        castling.black_queenside,
+    ];
+    rights
+        .iter()
+        .zip(KEYS.castling.iter())
+        .filter(|(has_right, _)| **has_right)
+        .fold(0, |hash, (_, key)| hash ^ key)
+}
+
+// The en passant file only counts when the side to move has a pawn that could take there.
+// Otherwise the position after a double push would never match the same position reached
+// some other way, and repetitions would get missed.
+pub fn en_passant_key(state: &GameState) -> u64 {
+    let en_passant = match state.en_passant {
+        Some(en_passant) => en_passant,
+        None => return 0,
+    };
+    let color = state.side_to_move;
+    // Pawns that could take on the square are where an enemy pawn standing there would capture
+    let takers = pawn_attacks(square_index(en_passant), color.opposite()) & state.board.pieces_of(color, PieceType::Pawn);
+    if takers == EMPTY {
+        0
+    } else {
+        KEYS.en_passant_files[en_passant.1 as usize]
+    }
+}
+
+impl GameState {
+    // Works the hash out from nothing. make_move keeps `hash` up to date on its own, so this is
+    // only needed after setting up or editing a position by hand.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = side_to_move_key(self.side_to_move) ^ castling_key(&self.castling) ^ en_passant_key(self);
+        for (pos, piece) in self.board.pieces() {
+            hash ^= piece_key(piece, square_index(pos));
+        }
+        hash
+    }
+}
@@ -0,0 +1,4 @@
+// Headless chess rules: the board model, FEN parsing and move validation.
+// Nothing in here depends on bevy, so it can be used from tests, CLIs and servers
+// without linking the renderer. The GUI in main.rs is just another consumer of it.
+pub mod core;
@@ -1,19 +1,102 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_mod_picking::*;
+use rust_chess_engine::core::{parse_fen, parse_pgn, PgnGame, OTHER_OTHER_TEST_FEN};
 
 mod pieces;
 use pieces::*;
 mod board;
+mod computer;
+use computer::ComputerPlugin;
+mod move_list;
+use move_list::MoveListPlugin;
+mod pgn_viewer;
+use pgn_viewer::PgnViewerPlugin;
 mod ui;
 use ui::UIPlugin;
-mod engine;
 
 use board::*;
 
 // From: https://caballerocoll.com/blog/bevy-chess-tutorial/
 
+// Reads every game out of the PGN file at `path`, or exits with an error
+fn load_pgn(path: &str) -> Vec<PgnGame> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+    match parse_pgn(&text) {
+        Ok(games) if games.is_empty() => {
+            eprintln!("{} doesn't have any games in it", path);
+            std::process::exit(1);
+        }
+        Ok(games) => games,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// What can be passed on the command line
+struct Options {
+    players: Players,
+    think_time: Duration,
+    // A PGN file to step through the games in instead of playing
+    pgn_path: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: rust_chess_engine [--computer white|black|both] [--think-time <seconds>] [games.pgn]");
+    std::process::exit(1);
+}
+
+fn parse_options() -> Options {
+    let mut options = Options { players: Players::default(), think_time: Duration::from_secs(2), pgn_path: None };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--computer" => match args.next().as_deref() {
+                Some("white") => options.players.white = PlayerKind::Computer,
+                Some("black") => options.players.black = PlayerKind::Computer,
+                Some("both") => {
+                    options.players.white = PlayerKind::Computer;
+                    options.players.black = PlayerKind::Computer;
+                }
+                _ => usage(),
+            },
+            "--think-time" => match args.next().and_then(|seconds| seconds.parse::<f64>().ok()) {
+                Some(seconds) if seconds > 0.0 => options.think_time = Duration::from_secs_f64(seconds),
+                _ => usage(),
+            },
+            _ if arg.starts_with("--") || options.pgn_path.is_some() => usage(),
+            _ => options.pgn_path = Some(arg),
+        }
+    }
+    options
+}
+
 fn main() {
-    App::build().
+    let options = parse_options();
+    let pgn_games = options.pgn_path.as_deref().map(load_pgn);
+
+    let game_state = match &pgn_games {
+        Some(games) => games[0].start,
+        None => match parse_fen(OTHER_OTHER_TEST_FEN) {
+            Ok(game_state) => game_state,
+            Err(error) => {
+                eprintln!("Couldn't load starting position: {}", error);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let mut app = App::build();
+    app.
     // Add 4x MSAA
     add_resource(Msaa {samples: 4}).
     add_resource(WindowDescriptor {
@@ -22,14 +105,23 @@ fn main() {
       height: 1000.,
       ..Default::default()
     })
+    // The position the board starts from. The plugins read this while they're being built.
+    .add_resource(game_state)
     .add_plugins(DefaultPlugins)
     .add_plugin(BoardPlugin)
     .add_plugin(PiecesPlugin)
     .add_plugin(PickingPlugin)
     .add_plugin(UIPlugin)
+    .add_plugin(MoveListPlugin)
+    .add_plugin(ComputerPlugin { players: options.players, think_time: options.think_time })
     // .add_plugin(DebugPickingPlugin)
-    .add_startup_system(setup.system())
-    .run();
+    .add_startup_system(setup.system());
+
+    if let Some(games) = pgn_games {
+        app.add_plugin(PgnViewerPlugin { games });
+    }
+
+    app.run();
 }
 
 fn setup(commands: &mut Commands) {
@@ -0,0 +1,297 @@
+use std::time::Duration;
+
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use rust_chess_engine::core::{Move, PieceColor};
+
+use crate::board::*;
+
+// The list of moves down the right side of the window, two to a row like a scoresheet. The
+// mouse wheel scrolls it. Clicking a move shows the position after it without touching the
+// game, and the Back to game button returns to where the game is.
+
+const VISIBLE_ROWS: usize = 20;
+
+// A look back at an earlier position, along with what's needed to get back to the game
+struct Review {
+    live_moves: Vec<Move>,
+    live_times: Vec<Duration>,
+    // How many moves into the game the board is showing
+    ply: usize,
+}
+
+#[derive(Default)]
+pub struct MoveList {
+    review: Option<Review>,
+    // The first row showing
+    scroll: usize,
+    // The game's length last frame, to notice new moves and scroll to them
+    last_length: usize,
+}
+
+impl MoveList {
+    // The moves and times of the game being played, while the board is showing an earlier position
+    pub fn live_game(&self) -> Option<(&[Move], &[Duration])> {
+        self.review.as_ref().map(|review| (review.live_moves.as_slice(), review.live_times.as_slice()))
+    }
+}
+
+// Components for the panel and the things on it that can be clicked
+struct MoveListPanel;
+enum MoveListButton {
+    // Go to the position after this many moves
+    Ply(usize),
+    BackToGame,
+}
+
+// Everything the panel shows, so it only gets rebuilt when one of them changes
+type PanelContents = (Vec<Move>, Option<usize>, usize);
+
+#[allow(clippy::too_many_arguments)]
+fn update_move_list(
+    commands: &mut Commands,
+    mut last_contents: Local<Option<PanelContents>>,
+    mut move_list: ResMut<MoveList>,
+    starting_position: Res<StartingPosition>,
+    move_history: Res<MoveHistory>,
+    asset_server: Res<AssetServer>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    panel_query: Query<Entity, With<MoveListPanel>>,
+) {
+    // While reviewing, the list is still the whole game and not just the moves up to the one showing
+    let (moves, current) = match &move_list.review {
+        Some(review) => (review.live_moves.clone(), review.ply),
+        None => (move_history.0.clone(), move_history.0.len()),
+    };
+
+    // A game starting with black to move leaves white's half of the first row empty
+    let offset = if starting_position.0.side_to_move == PieceColor::Black { 1 } else { 0 };
+    let rows = (moves.len() + offset).div_ceil(2);
+
+    // Keep the latest move in view as the game goes on
+    if move_list.review.is_none() && moves.len() != move_list.last_length {
+        move_list.scroll = rows.saturating_sub(VISIBLE_ROWS);
+    }
+    move_list.last_length = moves.len();
+    move_list.scroll = move_list.scroll.min(rows.saturating_sub(VISIBLE_ROWS));
+
+    let highlighted = current.checked_sub(1);
+    let contents = (moves, highlighted, move_list.scroll);
+    if last_contents.as_ref() == Some(&contents) {
+        return;
+    }
+    let (moves, highlighted, scroll) = contents.clone();
+    *last_contents = Some(contents);
+
+    for entity in panel_query.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    // Write the moves out from the start position to get their SAN
+    let mut state = starting_position.0;
+    let mut sans = Vec::with_capacity(moves.len());
+    for mv in moves.iter() {
+        sans.push(state.move_to_san(*mv));
+        state.make_move(*mv);
+    }
+    let first_move_number = starting_position.0.fullmove_number as usize;
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let background = color_materials.add(Color::rgba(0.1, 0.1, 0.1, 0.8).into());
+    let none = color_materials.add(Color::NONE.into());
+    let move_material = color_materials.add(Color::rgb(0.25, 0.25, 0.25).into());
+    let highlighted_material = color_materials.add(Color::rgb(0.6, 0.3, 0.1).into());
+    let text = |value: String| TextBundle {
+        text: Text {
+            value,
+            font: font.clone(),
+            style: TextStyle {
+                font_size: 22.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..Default::default()
+            },
+        },
+        ..Default::default()
+    };
+
+    let reviewing = move_list.review.is_some();
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    top: Val::Px(60.),
+                    ..Default::default()
+                },
+                // Children of a column go bottom to top, so this puts the first move up top
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(5.)),
+                ..Default::default()
+            },
+            material: background,
+            ..Default::default()
+        })
+        .with(MoveListPanel)
+        .with_children(|panel| {
+            if scroll > 0 {
+                panel.spawn(text("...".to_string()));
+            }
+            for row in scroll..(scroll + VISIBLE_ROWS).min(rows) {
+                panel
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: none.clone(),
+                        ..Default::default()
+                    })
+                    .with_children(|row_node| {
+                        row_node
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(50.), Val::Px(30.)),
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                material: none.clone(),
+                                ..Default::default()
+                            })
+                            .with_children(|number| {
+                                number.spawn(text(format!("{}.", first_move_number + row)));
+                            });
+                        for column in 0..2 {
+                            let style = Style {
+                                size: Size::new(Val::Px(100.), Val::Px(30.)),
+                                margin: Rect::all(Val::Px(2.)),
+                                padding: Rect::all(Val::Px(4.)),
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            };
+                            // White's half of a black-first game's first row is left blank
+                            let index = match (row * 2 + column).checked_sub(offset).filter(|index| *index < sans.len()) {
+                                Some(index) => index,
+                                None => {
+                                    row_node.spawn(NodeBundle { style, material: none.clone(), ..Default::default() });
+                                    continue;
+                                }
+                            };
+                            let material = if highlighted == Some(index) {
+                                highlighted_material.clone()
+                            } else {
+                                move_material.clone()
+                            };
+                            row_node
+                                .spawn(ButtonBundle { style, material, ..Default::default() })
+                                .with(MoveListButton::Ply(index + 1))
+                                .with_children(|button| {
+                                    button.spawn(text(sans[index].clone()));
+                                });
+                        }
+                    });
+            }
+            if scroll + VISIBLE_ROWS < rows {
+                panel.spawn(text("...".to_string()));
+            }
+            if reviewing {
+                panel
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(254.), Val::Px(40.)),
+                            margin: Rect::all(Val::Px(2.)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: highlighted_material.clone(),
+                        ..Default::default()
+                    })
+                    .with(MoveListButton::BackToGame)
+                    .with_children(|button| {
+                        button.spawn(text("Back to game".to_string()));
+                    });
+            }
+        });
+}
+
+fn scroll_move_list(
+    mut event_reader: Local<EventReader<MouseWheel>>,
+    events: Res<Events<MouseWheel>>,
+    mut move_list: ResMut<MoveList>,
+) {
+    for event in event_reader.iter(&events) {
+        if event.y > 0.0 {
+            move_list.scroll = move_list.scroll.saturating_sub(1);
+        } else if event.y < 0.0 {
+            // update_move_list keeps this from going past the end
+            move_list.scroll += 1;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn move_list_buttons(
+    mut move_list: ResMut<MoveList>,
+    mut read_only: ResMut<ReadOnlyBoard>,
+    starting_position: Res<StartingPosition>,
+    move_history: Res<MoveHistory>,
+    move_times: Res<MoveTimes>,
+    pending_promotion: Res<PendingPromotion>,
+    mut load_position_events: ResMut<Events<LoadPositionEvent>>,
+    query: Query<(&Interaction, &MoveListButton), Mutated<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            MoveListButton::Ply(ply) => {
+                // Games being stepped through from a PGN file have their own controls
+                if (read_only.0 && move_list.review.is_none()) || pending_promotion.0.is_some() {
+                    continue;
+                }
+                if move_list.review.is_none() {
+                    move_list.review = Some(Review {
+                        live_moves: move_history.0.clone(),
+                        live_times: move_times.times.clone(),
+                        ply: move_history.0.len(),
+                    });
+                }
+                let review = move_list.review.as_mut().unwrap();
+                if *ply > review.live_moves.len() {
+                    continue;
+                }
+                review.ply = *ply;
+                read_only.0 = true;
+                load_position_events.send(LoadPositionEvent {
+                    start: starting_position.0,
+                    moves: review.live_moves[..*ply].to_vec(),
+                    times: Vec::new(),
+                    review: true,
+                });
+            }
+            MoveListButton::BackToGame => {
+                if let Some(review) = move_list.review.take() {
+                    read_only.0 = false;
+                    load_position_events.send(LoadPositionEvent {
+                        start: starting_position.0,
+                        moves: review.live_moves,
+                        times: review.live_times,
+                        review: true,
+                    });
+                }
+            }
+        }
+    }
+}
+
+pub struct MoveListPlugin;
+impl Plugin for MoveListPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<MoveList>()
+            .add_system(update_move_list.system())
+            .add_system(scroll_move_list.system())
+            .add_system(move_list_buttons.system());
+    }
+}
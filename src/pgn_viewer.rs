@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use rust_chess_engine::core::PgnGame;
+
+use crate::board::*;
+
+// Steps through games loaded from a PGN file. Right and left arrow go forward and back a move,
+// Home and End jump to the start and end, and G brings back the game picker when the file had
+// more than one game in it.
+
+const GAMES_PER_PAGE: usize = 10;
+
+pub struct PgnViewer {
+    pub games: Vec<PgnGame>,
+    // The game on the board, or None while the picker is up
+    pub current: Option<usize>,
+    // How many of the current game's moves have been played on the board
+    pub ply: usize,
+    // Which page of games the picker is showing
+    page: usize,
+}
+
+impl PgnViewer {
+    fn game(&self) -> Option<&PgnGame> {
+        self.current.map(|index| &self.games[index])
+    }
+}
+
+fn game_title(game: &PgnGame) -> String {
+    format!(
+        "{} - {} ({}) {}",
+        game.tag("White").unwrap_or("?"),
+        game.tag("Black").unwrap_or("?"),
+        game.tag("Event").unwrap_or("?"),
+        game.result
+    )
+}
+
+// Component for the text saying which game and move are showing
+struct ViewerText;
+
+// Components for the game picker: the popup itself and its buttons
+struct GamePicker;
+enum GamePickerButton {
+    Game(usize),
+    PreviousPage,
+    NextPage,
+}
+
+// Viewing a game, not playing one
+fn lock_board(mut read_only: ResMut<ReadOnlyBoard>) {
+    read_only.0 = true;
+}
+
+fn init_viewer_text(commands: &mut Commands, asset_server: Res<AssetServer>, viewer: Res<PgnViewer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    top: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                value: viewer_text(&viewer),
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                style: TextStyle {
+                    font_size: 30.0,
+                    color: Color::rgb(0.8, 0.8, 0.8),
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        })
+        .with(ViewerText);
+}
+
+fn viewer_text(viewer: &PgnViewer) -> String {
+    match viewer.game() {
+        Some(game) => format!("{}, move {} of {}", game_title(game), viewer.ply, game.moves.len()),
+        None => "Pick a game".to_string(),
+    }
+}
+
+fn viewer_text_update(viewer: ChangedRes<PgnViewer>, mut query: Query<&mut Text, With<ViewerText>>) {
+    for mut text in query.iter_mut() {
+        text.value = viewer_text(&viewer);
+    }
+}
+
+fn step_through_game(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut viewer: ResMut<PgnViewer>,
+    mut play_move_events: ResMut<Events<PlayMoveEvent>>,
+    mut load_position_events: ResMut<Events<LoadPositionEvent>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::G) && viewer.games.len() > 1 {
+        viewer.current = None;
+        return;
+    }
+
+    let game = match viewer.game() {
+        Some(game) => game,
+        None => return,
+    };
+    let length = game.moves.len();
+
+    // Going forward is just the next move, so it can animate like any other
+    if keyboard_input.just_pressed(KeyCode::Right) && viewer.ply < length {
+        play_move_events.send(PlayMoveEvent(game.moves[viewer.ply].mv));
+        viewer.ply += 1;
+        return;
+    }
+
+    let ply = if keyboard_input.just_pressed(KeyCode::Left) {
+        viewer.ply.saturating_sub(1)
+    } else if keyboard_input.just_pressed(KeyCode::Home) {
+        0
+    } else if keyboard_input.just_pressed(KeyCode::End) {
+        length
+    } else {
+        return;
+    };
+    if ply == viewer.ply {
+        return;
+    }
+    load_position_events.send(LoadPositionEvent {
+        start: game.start,
+        moves: game.mainline()[..ply].to_vec(),
+        times: Vec::new(),
+        review: false,
+    });
+    viewer.ply = ply;
+}
+
+// Rebuild the picker whenever it's opened or changes page, and take it down once a game is picked
+fn show_game_picker(
+    commands: &mut Commands,
+    viewer: ChangedRes<PgnViewer>,
+    asset_server: Res<AssetServer>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    picker_query: Query<Entity, With<GamePicker>>,
+) {
+    for entity in picker_query.iter() {
+        commands.despawn_recursive(entity);
+    }
+    if viewer.current.is_some() {
+        return;
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let background = color_materials.add(Color::rgba(0.1, 0.1, 0.1, 0.9).into());
+    let button_material = color_materials.add(Color::rgb(0.3, 0.3, 0.3).into());
+
+    let first = viewer.page * GAMES_PER_PAGE;
+    let last = (first + GAMES_PER_PAGE).min(viewer.games.len());
+    let mut buttons: Vec<(GamePickerButton, String)> = (first..last)
+        .map(|index| (GamePickerButton::Game(index), format!("{}. {}", index + 1, game_title(&viewer.games[index]))))
+        .collect();
+    if first > 0 {
+        buttons.push((GamePickerButton::PreviousPage, "Previous games".to_string()));
+    }
+    if last < viewer.games.len() {
+        buttons.push((GamePickerButton::NextPage, "More games".to_string()));
+    }
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    top: Val::Px(70.),
+                    ..Default::default()
+                },
+                // Children of a column go bottom to top, so this puts the first game up top
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(5.)),
+                ..Default::default()
+            },
+            material: background,
+            ..Default::default()
+        })
+        .with(GamePicker)
+        .with_children(|parent| {
+            for (button, label) in buttons {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(700.), Val::Px(40.)),
+                            margin: Rect::all(Val::Px(5.)),
+                            padding: Rect::all(Val::Px(5.)),
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: button_material.clone(),
+                        ..Default::default()
+                    })
+                    .with(button)
+                    .with_children(|button| {
+                        button.spawn(TextBundle {
+                            text: Text {
+                                value: label,
+                                font: font.clone(),
+                                style: TextStyle {
+                                    font_size: 24.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                    ..Default::default()
+                                },
+                            },
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
+
+fn game_picker_buttons(
+    mut viewer: ResMut<PgnViewer>,
+    mut load_position_events: ResMut<Events<LoadPositionEvent>>,
+    query: Query<(&Interaction, &GamePickerButton), Mutated<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            GamePickerButton::Game(index) => {
+                viewer.current = Some(*index);
+                viewer.ply = 0;
+                load_position_events.send(LoadPositionEvent {
+                    start: viewer.games[*index].start,
+                    moves: Vec::new(),
+                    times: Vec::new(),
+                    review: false,
+                });
+            }
+            GamePickerButton::PreviousPage => viewer.page -= 1,
+            GamePickerButton::NextPage => viewer.page += 1,
+        }
+    }
+}
+
+pub struct PgnViewerPlugin {
+    pub games: Vec<PgnGame>,
+}
+
+impl Plugin for PgnViewerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // With only one game there's nothing to pick from
+        let current = if self.games.len() == 1 { Some(0) } else { None };
+        app.add_resource(PgnViewer { games: self.games.clone(), current, ply: 0, page: 0 })
+            .add_startup_system(lock_board.system())
+            .add_startup_system(init_viewer_text.system())
+            .add_system(viewer_text_update.system())
+            .add_system(step_through_game.system())
+            .add_system(show_game_picker.system())
+            .add_system(game_picker_buttons.system());
+    }
+}
@@ -1,13 +1,26 @@
-use crate::{board::*, pieces::*};
+use crate::board::*;
 use bevy::prelude::*;
+use rust_chess_engine::core::{Move, PieceType, PROMOTION_PIECES};
 
 // Component for Text Entity
 struct NextMoveText;
 
+// Components for the promotion picker: the popup itself and the button for each piece
+struct PromotionPicker;
+struct PromotionButton(PieceType);
+
+// The undo and redo buttons in the bottom corner
+enum HistoryButton {
+  Undo,
+  Redo,
+}
+
 // Initialize UiCamera and Text
 fn init_next_move_text(
   commands: &mut Commands,
   asset_server: ResMut<AssetServer>,
+  turn: Res<PlayerTurn>,
+  game_status: Res<GameStatus>,
   mut color_materials: ResMut<Assets<ColorMaterial>>,
 ) {
   let font = asset_server.load("fonts/FiraSans-Bold.ttf");
@@ -31,7 +44,7 @@ fn init_next_move_text(
   .with_children(|parent| {
     parent.spawn(TextBundle {
       text: Text {
-        value: "Next move: White".to_string(),
+        value: status_text(&turn, &game_status),
         font,
         style: TextStyle {
           font_size: 40.0,
@@ -45,19 +58,177 @@ fn init_next_move_text(
   });
 }
 
-// Update the text with the correct turn
+fn status_text(turn: &PlayerTurn, game_status: &GameStatus) -> String {
+  match game_status.outcome {
+    Some(outcome) => format!("{}!", outcome),
+    None if game_status.king_in_check.is_some() => format!("Next move: {} (check)", turn.0),
+    None => format!("Next move: {}", turn.0),
+  }
+}
+
+// Update the text with the correct turn, or the result once the game is over
 fn next_move_text_update (
-  turn: ChangedRes<PlayerTurn>,
+  turn: Res<PlayerTurn>,
+  game_status: ChangedRes<GameStatus>,
   mut query: Query<(&mut Text, &NextMoveText)>,
 ) {
   for (mut text, _tag) in query.iter_mut() {
-    text.value = format!(
-      "Next move: {}",
-      match turn.0 {
-        PieceColor::White => "White",
-        PieceColor::Black => "Black"
-      }
-    );
+    text.value = status_text(&turn, &game_status);
+  }
+}
+
+// Pop up the promotion picker when a pawn is waiting on one, and take it down afterwards
+fn show_promotion_picker(
+  commands: &mut Commands,
+  pending_promotion: ChangedRes<PendingPromotion>,
+  asset_server: Res<AssetServer>,
+  mut color_materials: ResMut<Assets<ColorMaterial>>,
+  picker_query: Query<Entity, With<PromotionPicker>>,
+) {
+  if pending_promotion.0.is_none() {
+    for entity in picker_query.iter() {
+      commands.despawn_recursive(entity);
+    }
+    return;
+  }
+  if picker_query.iter().next().is_some() {
+    return;
+  }
+
+  let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+  let background = color_materials.add(Color::rgba(0.1, 0.1, 0.1, 0.9).into());
+  let button_material = color_materials.add(Color::rgb(0.3, 0.3, 0.3).into());
+
+  commands.spawn(NodeBundle {
+    style: Style {
+      position_type: PositionType::Absolute,
+      position: Rect {
+        left: Val::Px(10.),
+        top: Val::Px(70.),
+        ..Default::default()
+      },
+      padding: Rect::all(Val::Px(5.)),
+      ..Default::default()
+    },
+    material: background,
+    ..Default::default()
+  })
+  .with(PromotionPicker)
+  .with_children(|parent| {
+    for piece_type in PROMOTION_PIECES.iter() {
+      parent.spawn(ButtonBundle {
+        style: Style {
+          size: Size::new(Val::Px(120.), Val::Px(50.)),
+          margin: Rect::all(Val::Px(5.)),
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          ..Default::default()
+        },
+        material: button_material.clone(),
+        ..Default::default()
+      })
+      .with(PromotionButton(*piece_type))
+      .with_children(|button| {
+        button.spawn(TextBundle {
+          text: Text {
+            value: piece_type.to_string(),
+            font: font.clone(),
+            style: TextStyle {
+              font_size: 30.0,
+              color: Color::rgb(0.9, 0.9, 0.9),
+              ..Default::default()
+            },
+          },
+          ..Default::default()
+        });
+      });
+    }
+  });
+}
+
+// Finish the pending promotion with whichever piece got clicked
+fn promotion_picker_buttons(
+  mut pending_promotion: ResMut<PendingPromotion>,
+  mut play_move_events: ResMut<Events<PlayMoveEvent>>,
+  query: Query<(&Interaction, &PromotionButton), Mutated<Interaction>>,
+) {
+  for (interaction, button) in query.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+    if let Some(mv) = pending_promotion.0.take() {
+      play_move_events.send(PlayMoveEvent(Move::with_promotion(mv.from, mv.to, button.0)));
+    }
+  }
+}
+
+fn init_history_buttons(
+  commands: &mut Commands,
+  asset_server: Res<AssetServer>,
+  mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+  let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+  let background = color_materials.add(Color::NONE.into());
+  let button_material = color_materials.add(Color::rgb(0.3, 0.3, 0.3).into());
+
+  commands.spawn(NodeBundle {
+    style: Style {
+      position_type: PositionType::Absolute,
+      position: Rect {
+        left: Val::Px(10.),
+        bottom: Val::Px(10.),
+        ..Default::default()
+      },
+      ..Default::default()
+    },
+    material: background,
+    ..Default::default()
+  })
+  .with_children(|parent| {
+    for (button, label) in [(HistoryButton::Undo, "Undo"), (HistoryButton::Redo, "Redo")] {
+      parent.spawn(ButtonBundle {
+        style: Style {
+          size: Size::new(Val::Px(120.), Val::Px(50.)),
+          margin: Rect::all(Val::Px(5.)),
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          ..Default::default()
+        },
+        material: button_material.clone(),
+        ..Default::default()
+      })
+      .with(button)
+      .with_children(|button| {
+        button.spawn(TextBundle {
+          text: Text {
+            value: label.to_string(),
+            font: font.clone(),
+            style: TextStyle {
+              font_size: 30.0,
+              color: Color::rgb(0.9, 0.9, 0.9),
+              ..Default::default()
+            },
+          },
+          ..Default::default()
+        });
+      });
+    }
+  });
+}
+
+fn history_buttons(
+  mut undo_events: ResMut<Events<UndoMoveEvent>>,
+  mut redo_events: ResMut<Events<RedoMoveEvent>>,
+  query: Query<(&Interaction, &HistoryButton), Mutated<Interaction>>,
+) {
+  for (interaction, button) in query.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+    match button {
+      HistoryButton::Undo => undo_events.send(UndoMoveEvent),
+      HistoryButton::Redo => redo_events.send(RedoMoveEvent),
+    }
   }
 }
 
@@ -65,6 +236,10 @@ pub struct UIPlugin;
 impl Plugin for UIPlugin {
   fn build(&self, app: &mut AppBuilder) {
     app.add_startup_system(init_next_move_text.system())
-      .add_system(next_move_text_update.system());
+      .add_system(next_move_text_update.system())
+      .add_system(show_promotion_picker.system())
+      .add_system(promotion_picker_buttons.system())
+      .add_startup_system(init_history_buttons.system())
+      .add_system(history_buttons.system());
   }
 }
@@ -0,0 +1,13 @@
+use rust_chess_engine::core::GameState;
+
+// Plays `moves`, written the way UCI writes them, pushing the hash of each position left
+// behind onto `history`
+pub fn play(state: &mut GameState, history: &mut Vec<u64>, moves: &[&str]) {
+    for text in moves {
+        let mv = state
+            .parse_uci_move(text)
+            .unwrap_or_else(|error| panic!("{} in {}", error, state.to_fen()));
+        history.push(state.hash);
+        state.make_move(mv);
+    }
+}
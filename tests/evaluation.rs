@@ -0,0 +1,78 @@
+use rust_chess_engine::core::{evaluate, evaluate_with, parse_fen, EvalParams, STARTING_BOARD_FEN};
+
+fn eval(fen: &str) -> i32 {
+    evaluate(&parse_fen(fen).unwrap())
+}
+
+#[test]
+fn symmetric() {
+    assert_eq!(eval(STARTING_BOARD_FEN), 0);
+
+    // The same position with the colors swapped and the board flipped scores the same for the side to move
+    let italian = "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+    let flipped = "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/2N5/PPPP1PPP/R1BQK1NR b KQkq - 4 4";
+    assert_eq!(eval(italian), eval(flipped));
+
+    // And from the other side it's the same number the other way round
+    let other_side = "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 4 4";
+    assert_eq!(eval(other_side), -eval(italian));
+}
+
+#[test]
+fn material_only() {
+    let params = EvalParams {
+        middlegame_tables: [[0; 64]; 6],
+        endgame_tables: [[0; 64]; 6],
+        mobility: [(0, 0); 6],
+        doubled_pawn: (0, 0),
+        isolated_pawn: (0, 0),
+        passed_pawn: [(0, 0); 8],
+        pawn_shield: [(0, 0); 2],
+        king_zone_attack: (0, 0),
+        ..Default::default()
+    };
+    // Pawns don't count towards the phase, so a pawn ending is all endgame
+    let state = parse_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+    assert_eq!(evaluate_with(&state, &params), params.material[5].1);
+    // Anything in between is a blend. A rook off takes 2 of the 24 phase points.
+    let state = parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 b Qkq - 0 1").unwrap();
+    let (middlegame, endgame) = params.material[4];
+    assert_eq!(evaluate_with(&state, &params), (middlegame * 22 + endgame * 2) / 24);
+}
+
+#[test]
+fn pawn_structure() {
+    // Only the passed pawn bonus, so nothing else can make up the difference
+    let params = EvalParams {
+        middlegame_tables: [[0; 64]; 6],
+        endgame_tables: [[0; 64]; 6],
+        mobility: [(0, 0); 6],
+        doubled_pawn: (0, 0),
+        isolated_pawn: (0, 0),
+        pawn_shield: [(0, 0); 2],
+        king_zone_attack: (0, 0),
+        ..Default::default()
+    };
+    let eval_with = |fen: &str| evaluate_with(&parse_fen(fen).unwrap(), &params);
+    // Black's pawn on c7 stops the d5 pawn, and can't get past it either
+    let stopped = eval_with("r3k3/2p5/8/3P4/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(stopped, 0);
+    // Over on h7 both pawns are passed: white's on its fifth rank, black's still on its second.
+    // The rooks are 4 of the 24 phase points.
+    let passed = eval_with("r3k3/7p/8/3P4/8/8/8/R3K3 w - - 0 1");
+    let middlegame = params.passed_pawn[4].0 - params.passed_pawn[1].0;
+    let endgame = params.passed_pawn[4].1 - params.passed_pawn[1].1;
+    assert!(middlegame > 0 && endgame > 0);
+    assert_eq!(passed - stopped, (middlegame * 4 + endgame * 20) / 24);
+
+    // Doubled and isolated pawns are worse than connected ones
+    assert!(eval("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1") > eval("4k3/pp6/8/8/8/P7/P7/4K3 w - - 0 1"));
+}
+
+#[test]
+fn king_safety() {
+    // Castled behind its pawns beats the same king with the pawns pushed away, with queens on
+    let sheltered = "r2q1rk1/ppp2ppp/8/8/8/8/PPP2PPP/R2Q1RK1 w - - 0 1";
+    let exposed = "r2q1rk1/ppp2ppp/8/8/8/5PPP/PPP5/R2Q1RK1 w - - 0 1";
+    assert!(eval(sheltered) > eval(exposed));
+}
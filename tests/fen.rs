@@ -0,0 +1,94 @@
+use rust_chess_engine::core::{parse_fen, FenError, FenField, OTHER_OTHER_TEST_FEN, OTHER_TEST_FEN, STARTING_BOARD_FEN};
+
+#[test]
+fn round_trips() {
+    let fens = [
+        STARTING_BOARD_FEN,
+        OTHER_TEST_FEN,
+        OTHER_OTHER_TEST_FEN,
+        // Black to move with an en passant capture on offer
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/5N2/PPPP1PPP/RNBQKB1R b KQkq e3 0 3",
+        // Some of the castling rights already gone
+        "r3k2r/pppq1ppp/2n2n2/3pp3/3PP3/2N2N2/PPPQ1PPP/R3K2R w Kq - 4 9",
+        "4k2r/8/8/8/8/8/8/R3K3 b Qk - 12 40",
+    ];
+    for fen in fens.iter() {
+        assert_eq!(parse_fen(fen).unwrap().to_fen(), *fen);
+    }
+}
+
+#[test]
+fn pawns_on_back_ranks() {
+    // Used to get as far as the move generator, which stepped a pawn off the board
+    assert_eq!(parse_fen("P5k1/8/8/8/8/8/8/6K1 w - - 0 1"), Err(FenError::PawnOnBackRank { rank: 7, file: 0 }));
+    assert_eq!(parse_fen("6k1/8/8/8/8/8/8/4p1K1 b - - 0 1"), Err(FenError::PawnOnBackRank { rank: 0, file: 4 }));
+    assert_eq!(
+        parse_fen("P5k1/8/8/8/8/8/8/6K1 w - - 0 1").unwrap_err().to_string(),
+        "pawn on a8, which is a back rank"
+    );
+}
+
+#[test]
+fn reports_bad_fields() {
+    let bad_character = |field, character| Err(FenError::InvalidCharacter { field, character });
+    let bad_number = |field, value: &str| Err(FenError::InvalidNumber { field, value: value.to_string() });
+
+    assert_eq!(parse_fen(""), Err(FenError::MissingField(FenField::PiecePlacement)));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8"), Err(FenError::MissingField(FenField::SideToMove)));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w"), Err(FenError::MissingField(FenField::Castling)));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w -"), Err(FenError::MissingField(FenField::EnPassant)));
+    // The clocks can be left off
+    assert!(parse_fen("8/8/8/8/8/8/8/8 w - -").is_ok());
+    assert_eq!(parse_fen(&format!("{} extra", STARTING_BOARD_FEN)), Err(FenError::TooManyFields(7)));
+
+    assert_eq!(
+        parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1"),
+        bad_character(FenField::PiecePlacement, 'X')
+    );
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 x - - 0 1"), bad_character(FenField::SideToMove, 'x'));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 white - - 0 1"), bad_character(FenField::SideToMove, 'h'));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w KX - 0 1"), bad_character(FenField::Castling, 'X'));
+    // Each right only once
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w KK - 0 1"), bad_character(FenField::Castling, 'K'));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w - i3 0 1"), bad_character(FenField::EnPassant, 'i'));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w - e3x 0 1"), bad_character(FenField::EnPassant, 'x'));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w - -1 0 1"), bad_character(FenField::EnPassant, '-'));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w - - -1 1"), bad_number(FenField::HalfmoveClock, "-1"));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8 w - - 0 one"), bad_number(FenField::FullmoveNumber, "one"));
+}
+
+#[test]
+fn en_passant_square_has_to_be_behind_a_double_push() {
+    // Only the third and sixth ranks can have a target square
+    assert_eq!(
+        parse_fen("8/8/8/8/8/8/8/8 w - e4 0 1"),
+        Err(FenError::InvalidCharacter { field: FenField::EnPassant, character: '4' })
+    );
+    assert_eq!(
+        parse_fen("8/8/8/8/8/8/8/8 b - e1 0 1"),
+        Err(FenError::InvalidCharacter { field: FenField::EnPassant, character: '1' })
+    );
+    assert_eq!(
+        parse_fen("8/8/8/8/8/8/8/8 w - e 0 1"),
+        Err(FenError::InvalidCharacter { field: FenField::EnPassant, character: '-' })
+    );
+    assert!(parse_fen("8/8/8/8/8/8/8/8 w - e6 0 1").is_ok());
+}
+
+#[test]
+fn malformed_ranks() {
+    // All of these used to panic or quietly put pieces in the wrong place
+    assert_eq!(parse_fen("8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongRankCount(7)));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongRankCount(9)));
+    assert_eq!(parse_fen("rnbqkbnrr/8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongRankLength { rank: 7, files: 9 }));
+    assert_eq!(parse_fen("8/8/8/8/8/8/8/44k w - - 0 1"), Err(FenError::WrongRankLength { rank: 0, files: 9 }));
+    assert_eq!(parse_fen("8/8/8/8/7/8/8/8 w - - 0 1"), Err(FenError::WrongRankLength { rank: 3, files: 7 }));
+    assert_eq!(
+        parse_fen("8/8/8/8/9/8/8/8 w - - 0 1"),
+        Err(FenError::InvalidCharacter { field: FenField::PiecePlacement, character: '9' })
+    );
+    assert_eq!(
+        parse_fen("8/8/8/8/08/8/8/8 w - - 0 1"),
+        Err(FenError::InvalidCharacter { field: FenField::PiecePlacement, character: '0' })
+    );
+}
@@ -0,0 +1,51 @@
+use rust_chess_engine::core::{generate_legal_moves, parse_fen};
+
+// Every legal move in `fen`, written the way UCI writes them and sorted
+fn legal_moves(fen: &str) -> Vec<String> {
+    let mut moves: Vec<String> = generate_legal_moves(&parse_fen(fen).unwrap())
+        .iter()
+        .map(|mv| mv.to_string())
+        .collect();
+    moves.sort();
+    moves
+}
+
+fn moves_from(fen: &str, square: &str) -> Vec<String> {
+    legal_moves(fen).into_iter().filter(|mv| mv.starts_with(square)).collect()
+}
+
+#[test]
+fn pinned_pieces() {
+    // The bishop can't leave the file it's shielding the king on
+    assert!(moves_from("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1", "e2").is_empty());
+    // A rook pinned along a file can still slide along it, as far as taking the pinner
+    assert_eq!(
+        moves_from("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1", "e2"),
+        ["e2e3", "e2e4", "e2e5", "e2e6", "e2e7"]
+    );
+    // Taking en passant would take both pawns off the rank and leave the king to the rook
+    assert_eq!(moves_from("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1", "e5"), ["e5e6"]);
+}
+
+#[test]
+fn getting_out_of_check() {
+    // Step off the file or block it, nothing else
+    assert_eq!(
+        legal_moves("4k3/4r3/8/8/8/8/1B6/4K3 w - - 0 1"),
+        ["b2e5", "e1d1", "e1d2", "e1f1", "e1f2"]
+    );
+    // Against two checkers at once the queen taking one or blocking the other doesn't help, so only
+    // the king can move
+    assert_eq!(legal_moves("k3r3/8/8/1Q6/8/3n4/8/4K3 w - - 0 1"), ["e1d1", "e1d2", "e1f1"]);
+}
+
+#[test]
+fn not_moving_into_check() {
+    // The kings can't stand next to each other
+    assert_eq!(moves_from("8/8/8/3k4/8/3K4/8/8 w - - 0 1", "d3"), ["d3c2", "d3c3", "d3d2", "d3e2", "d3e3"]);
+    // Nor can the king take a piece that's defended
+    assert!(!legal_moves("4k3/8/8/8/8/8/3q4/3qK3 w - - 0 1").contains(&"e1d2".to_string()));
+    // Castling can't pass through an attacked square
+    assert!(!legal_moves("4kr2/8/8/8/8/8/8/4K2R w K - 0 1").contains(&"e1g1".to_string()));
+    assert!(legal_moves("4k3/8/8/8/8/8/8/4K2R w K - 0 1").contains(&"e1g1".to_string()));
+}
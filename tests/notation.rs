@@ -0,0 +1,70 @@
+use rust_chess_engine::core::{parse_fen, GameState, MoveParseError, STARTING_BOARD_FEN};
+
+// Parses `san`, checks it comes back out the same, and hands back the move in UCI form
+fn round_trip(state: &GameState, san: &str) -> String {
+    let mv = state.parse_san(san).unwrap_or_else(|error| panic!("{}", error));
+    assert_eq!(state.move_to_san(mv), san);
+    assert_eq!(state.parse_uci_move(&mv.to_string()), Ok(mv));
+    mv.to_string()
+}
+
+#[test]
+fn pawn_and_piece_moves() {
+    let start = parse_fen(STARTING_BOARD_FEN).unwrap();
+    assert_eq!(round_trip(&start, "e4"), "e2e4");
+    assert_eq!(round_trip(&start, "Nf3"), "g1f3");
+    assert_eq!(start.parse_san("Ng1-f3").unwrap().to_string(), "g1f3");
+    assert_eq!(start.parse_san("e5"), Err(MoveParseError::IllegalMove("e5".to_string())));
+    assert_eq!(start.parse_san("Qz9"), Err(MoveParseError::InvalidSyntax("Qz9".to_string())));
+}
+
+#[test]
+fn disambiguation() {
+    // Knights on b8 and f6 can both reach d7
+    let state = parse_fen("rnbqkb1r/ppp2ppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(state.parse_san("Nd7"), Err(MoveParseError::AmbiguousMove("Nd7".to_string())));
+    assert_eq!(round_trip(&state, "Nbd7"), "b8d7");
+    assert_eq!(round_trip(&state, "Nfd7"), "f6d7");
+    // Only the f6 knight reaches d5, so it needs no help
+    assert_eq!(round_trip(&state, "Nd5"), "f6d5");
+
+    // Rooks on a1 and a5 share a file, so the rank tells them apart
+    let white = parse_fen("4k3/8/8/R7/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert_eq!(round_trip(&white, "R1a3"), "a1a3");
+    assert_eq!(round_trip(&white, "R5a3"), "a5a3");
+
+    // Three queens that share files and ranks need the whole square
+    let queens = parse_fen("8/8/1k6/8/Q6Q/8/8/4K2Q w - - 0 1").unwrap();
+    assert_eq!(round_trip(&queens, "Qh4e4"), "h4e4");
+    assert_eq!(round_trip(&queens, "Qae4"), "a4e4");
+    assert_eq!(round_trip(&queens, "Q1e4"), "h1e4");
+}
+
+#[test]
+fn captures_en_passant_and_promotion() {
+    let state = parse_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+    assert_eq!(round_trip(&state, "exf6"), "e5f6");
+    assert_eq!(state.parse_san("exf6 e.p.").unwrap().to_string(), "e5f6");
+    assert_eq!(state.parse_san("exf6e.p.").unwrap().to_string(), "e5f6");
+
+    let promotion = parse_fen("4k3/3P4/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    // d8 is next to the king on e8, so promoting there gives check
+    assert_eq!(round_trip(&promotion, "d8=Q+"), "d7d8q");
+    assert_eq!(round_trip(&promotion, "d8=N"), "d7d8n");
+    assert_eq!(promotion.parse_san("d8Q").unwrap().to_string(), "d7d8q");
+    assert!(promotion.parse_san("d8").is_err());
+    assert!(promotion.parse_uci_move("d7d8k").is_err());
+}
+
+#[test]
+fn castling_check_and_mate() {
+    let state = parse_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(round_trip(&state, "O-O"), "e1g1");
+    assert_eq!(round_trip(&state, "O-O-O"), "e1c1");
+    assert_eq!(state.parse_san("0-0-0").unwrap().to_string(), "e1c1");
+
+    // Fool's mate
+    let mate = parse_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2").unwrap();
+    assert_eq!(round_trip(&mate, "Qh4#"), "d8h4");
+    assert_eq!(mate.parse_san("Qh4+!!").unwrap().to_string(), "d8h4");
+}
@@ -0,0 +1,81 @@
+use rust_chess_engine::core::{
+    game_outcome, parse_fen, repetition_count, DrawReason, GameOutcome, PieceColor, STARTING_BOARD_FEN,
+};
+
+mod common;
+use common::play;
+
+const KNIGHT_DANCE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+#[test]
+fn repetitions() {
+    let mut state = parse_fen(STARTING_BOARD_FEN).unwrap();
+    let mut history = Vec::new();
+
+    play(&mut state, &mut history, &KNIGHT_DANCE);
+    assert_eq!(repetition_count(&state, &history), 2);
+    assert_eq!(game_outcome(&state, &history), None);
+
+    play(&mut state, &mut history, &KNIGHT_DANCE);
+    assert_eq!(repetition_count(&state, &history), 3);
+    assert_eq!(game_outcome(&state, &history), Some(GameOutcome::Draw(DrawReason::ThreefoldRepetition)));
+
+    play(&mut state, &mut history, &KNIGHT_DANCE);
+    play(&mut state, &mut history, &KNIGHT_DANCE);
+    assert_eq!(game_outcome(&state, &history), Some(GameOutcome::Draw(DrawReason::FivefoldRepetition)));
+}
+
+#[test]
+fn repetitions_need_the_same_castling_rights() {
+    let mut state = parse_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+    let mut history = Vec::new();
+    // The rooks come back, but the rights they had don't
+    play(&mut state, &mut history, &["h1g1", "h8g8", "g1h1", "g8h8"]);
+    play(&mut state, &mut history, &["h1g1", "h8g8", "g1h1", "g8h8"]);
+    assert_eq!(repetition_count(&state, &history), 2);
+    assert_eq!(game_outcome(&state, &history), None);
+}
+
+#[test]
+fn move_rules() {
+    let fifty = parse_fen("8/8/4k3/8/8/3K4/3R4/8 w - - 100 80").unwrap();
+    assert_eq!(game_outcome(&fifty, &[]), Some(GameOutcome::Draw(DrawReason::FiftyMoveRule)));
+
+    let seventy_five = parse_fen("8/8/4k3/8/8/3K4/3R4/8 w - - 150 105").unwrap();
+    assert_eq!(game_outcome(&seventy_five, &[]), Some(GameOutcome::Draw(DrawReason::SeventyFiveMoveRule)));
+
+    let not_yet = parse_fen("8/8/4k3/8/8/3K4/3R4/8 w - - 99 80").unwrap();
+    assert_eq!(game_outcome(&not_yet, &[]), None);
+
+    // Mate on the last move wins over the draw
+    let mate = parse_fen("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 100 80").unwrap();
+    assert_eq!(game_outcome(&mate, &[]), Some(GameOutcome::Checkmate(PieceColor::White)));
+}
+
+#[test]
+fn stalemate() {
+    // Black's king has nowhere to go and nothing else to move, but isn't in check
+    let stalemate = parse_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(game_outcome(&stalemate, &[]), Some(GameOutcome::Stalemate));
+
+    // With a pawn that can still move, it's just a bad position
+    let pawn_to_push = parse_fen("7k/5Q2/6K1/8/8/p7/8/8 b - - 0 1").unwrap();
+    assert_eq!(game_outcome(&pawn_to_push, &[]), None);
+}
+
+#[test]
+fn insufficient_material() {
+    let outcome = |fen: &str| game_outcome(&parse_fen(fen).unwrap(), &[]);
+    let dead = Some(GameOutcome::Draw(DrawReason::InsufficientMaterial));
+
+    assert_eq!(outcome("8/8/4k3/8/8/3K4/8/8 w - - 0 1"), dead);
+    assert_eq!(outcome("8/8/4k3/8/8/3K4/3N4/8 w - - 0 1"), dead);
+    assert_eq!(outcome("8/8/4k3/8/8/3K4/3b4/8 w - - 0 1"), dead);
+    // c1 and f8 are both dark squares
+    assert_eq!(outcome("5b2/8/4k3/8/8/3K4/8/2B5 w - - 0 1"), dead);
+
+    // Bishops on opposite colors, two knights, or anything heavier can still mate
+    assert_eq!(outcome("4b3/8/4k3/8/8/3K4/8/2B5 w - - 0 1"), None);
+    assert_eq!(outcome("8/8/4k3/8/8/3K4/3NN3/8 w - - 0 1"), None);
+    assert_eq!(outcome("8/8/4k3/8/8/3K4/3P4/8 w - - 0 1"), None);
+}
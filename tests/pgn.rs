@@ -0,0 +1,166 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use rust_chess_engine::core::{
+    parse_fen, parse_pgn, pgn_date, GameResult, MoveParseError, PgnError, PgnGame, STARTING_BOARD_FEN,
+};
+
+const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 {This is a weak move already.} 4. dxe5 Bxf3
+5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5?! 10. Nxb5! cxb5
+11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7
+16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+#[test]
+fn reads_tags_moves_and_result() {
+    let games = parse_pgn(OPERA_GAME).unwrap();
+    assert_eq!(games.len(), 1);
+    let game = &games[0];
+
+    assert_eq!(game.tag("White"), Some("Paul Morphy"));
+    assert_eq!(game.tag("Black"), Some("Duke Karl / Count Isouard"));
+    assert_eq!(game.tags.len(), 7);
+    assert_eq!(game.result, GameResult::WhiteWins);
+
+    assert_eq!(game.moves.len(), 33);
+    assert_eq!(game.moves[5].comments, vec!["This is a weak move already.".to_string()]);
+    assert_eq!(game.moves[17].nags, vec![6]);
+    assert_eq!(game.moves[18].nags, vec![1]);
+    assert_eq!(game.moves[22].san, "O-O-O");
+    assert_eq!(game.moves.last().unwrap().san, "Rd8#");
+}
+
+#[test]
+fn reads_nested_variations_and_comments() {
+    let pgn = r#"
+[Event "Variations"]
+[Result "*"]
+
+{Before any moves} 1. e4 $1 (1. d4 d5 (1... Nf6 2. c4) 2. c4) 1... c5 ; to the end of the line
+2. Nf3 (2. Nc3 {closed} Nc6 (2... d6)) *
+"#;
+    let game = &parse_pgn(pgn).unwrap()[0];
+    assert_eq!(game.result, GameResult::Unknown);
+    assert_eq!(game.moves.len(), 3);
+
+    let e4 = &game.moves[0];
+    assert_eq!(e4.comments_before, vec!["Before any moves".to_string()]);
+    assert_eq!(e4.nags, vec![1]);
+    let d4_line = &e4.variations[0];
+    assert_eq!(d4_line.iter().map(|mv| mv.san.as_str()).collect::<Vec<_>>(), ["d4", "d5", "c4"]);
+    // The variation inside it replaces 1... d5, so it's played from after 1. d4
+    assert_eq!(d4_line[1].variations[0][0].san, "Nf6");
+
+    assert_eq!(game.moves[1].comments, vec!["to the end of the line".to_string()]);
+    let nc3_line = &game.moves[2].variations[0];
+    assert_eq!(nc3_line[0].comments, vec!["closed".to_string()]);
+    assert_eq!(nc3_line[1].variations[0][0].san, "d6");
+}
+
+#[test]
+fn reads_several_games_and_custom_starts() {
+    let pgn = r#"
+[Event "One"]
+1. e4 e5 1/2-1/2
+
+[Event "Two"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 40"]
+40. e4 Kd7 41. e5 0-1
+
+[Event "Three"]
+[Result "1-0"]
+1. d4
+"#;
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].result, GameResult::Draw);
+    assert_eq!(games[1].start.fullmove_number, 40);
+    assert_eq!(games[1].mainline().len(), 3);
+    assert_eq!(games[1].result, GameResult::BlackWins);
+    // No result at the end of the moves, so it comes from the tag
+    assert_eq!(games[2].result, GameResult::WhiteWins);
+}
+
+#[test]
+fn reports_bad_input() {
+    assert_eq!(
+        parse_pgn("1. e4 e5 2. Ke3 *"),
+        Err(PgnError::InvalidMove { move_number: 2, error: MoveParseError::IllegalMove("Ke3".to_string()) })
+    );
+    assert_eq!(parse_pgn("1. e4 (1. d4 *"), Err(PgnError::UnexpectedToken("*".to_string())));
+    assert_eq!(parse_pgn("1. e4 (1. d4"), Err(PgnError::UnterminatedVariation));
+    assert_eq!(parse_pgn("1. e4 {oops"), Err(PgnError::UnterminatedComment));
+    assert_eq!(parse_pgn("1. e4 ) *"), Err(PgnError::UnexpectedToken(")".to_string())));
+    assert_eq!(parse_pgn("[Event Paris]"), Err(PgnError::InvalidTag("Event Paris".to_string())));
+}
+
+#[test]
+fn exports_what_it_reads() {
+    let pgn = r#"[Event "Variations"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "A \"quoted\" name"]
+[Black "?"]
+[Result "*"]
+[Annotator "Someone"]
+
+{Before any moves} 1. e4 $1 (1. d4 d5 (1... Nf6 2. c4) 2. c4) 1... c5
+{A comment} 2. Nf3 (2. Nc3 {closed} 2... Nc6 (2... d6)) 2... d6
+{[%clk 0:04:58.5] [%emt 0:00:03]} *
+"#;
+    let game = &parse_pgn(pgn).unwrap()[0];
+    assert_eq!(game.tag("White"), Some("A \"quoted\" name"));
+    assert_eq!(game.moves[3].clock, Some(Duration::from_millis(298_500)));
+    assert_eq!(game.moves[3].elapsed, Some(Duration::from_secs(3)));
+    assert!(game.moves[3].comments.is_empty());
+
+    assert_eq!(game.to_string(), pgn);
+}
+
+#[test]
+fn exports_new_games() {
+    let start = parse_fen(STARTING_BOARD_FEN).unwrap();
+    let opera = &parse_pgn(OPERA_GAME).unwrap()[0];
+    let mut game = PgnGame::new(start, &opera.mainline());
+    game.set_tag("Event", "Paris");
+    game.result = GameResult::WhiteWins;
+    game.moves[0].clock = Some(Duration::from_secs(300));
+
+    let exported = game.to_string();
+    assert!(exported.starts_with(
+        "[Event \"Paris\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n[Black \"?\"]\n[Result \"1-0\"]\n\n1. e4 {[%clk 0:05:00]} 1... e5 2. Nf3"
+    ));
+    assert!(exported.lines().all(|line| line.len() <= 80));
+    assert!(exported.ends_with("17. Rd8# 1-0\n"));
+    assert_eq!(parse_pgn(&exported).unwrap()[0].mainline(), opera.mainline());
+}
+
+#[test]
+fn exports_custom_starting_positions() {
+    let start = parse_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+    let kd7 = start.parse_san("Kd7").unwrap();
+    let game = PgnGame::new(start, &[kd7]);
+
+    assert_eq!(game.tag("SetUp"), Some("1"));
+    assert_eq!(game.tag("FEN"), Some("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40"));
+    assert!(game.to_string().ends_with("\n40... Kd7 *\n"));
+
+    // The usual start doesn't need them
+    assert_eq!(PgnGame::new(parse_fen(STARTING_BOARD_FEN).unwrap(), &[]).tag("FEN"), None);
+}
+
+#[test]
+fn formats_dates() {
+    assert_eq!(pgn_date(UNIX_EPOCH), "1970.01.01");
+    assert_eq!(pgn_date(UNIX_EPOCH + Duration::from_secs(1_615_680_000)), "2021.03.14");
+    assert_eq!(pgn_date(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000.02.29");
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use rust_chess_engine::core::{parse_fen, Score, Search, SearchLimits, SearchResult, STARTING_BOARD_FEN};
+
+fn search(fen: &str, limits: SearchLimits) -> SearchResult {
+    let state = parse_fen(fen).unwrap();
+    Search::default().search(&state, &[], &limits, |_| {})
+}
+
+fn to_depth(depth: u32) -> SearchLimits {
+    SearchLimits { depth: Some(depth), ..Default::default() }
+}
+
+fn pv(result: &SearchResult) -> Vec<String> {
+    result.pv.iter().map(|mv| mv.to_string()).collect()
+}
+
+#[test]
+fn finds_mates() {
+    let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", to_depth(3));
+    assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+    assert_eq!(result.score, Score::Mate(1));
+
+    // Ra7 boxes the king in and Rb8 mates, whatever black does in between
+    let result = search("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", to_depth(5));
+    assert_eq!(result.score, Score::Mate(2));
+    assert_eq!(pv(&result).len(), 3);
+
+    // And the other way round
+    let result = search("7k/R7/8/8/8/8/8/1R4K1 b - - 0 1", to_depth(4));
+    assert_eq!(result.score, Score::Mate(-1));
+    assert_eq!(pv(&result), ["h8g8", "b1b8"]);
+}
+
+#[test]
+fn takes_free_material() {
+    let result = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", to_depth(3));
+    assert_eq!(result.best_move.unwrap().to_string(), "d2d5");
+    assert!(matches!(result.score, Score::Centipawns(cp) if cp > 300));
+}
+
+#[test]
+fn no_moves() {
+    // Stalemate
+    let result = search("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", to_depth(3));
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, Score::Centipawns(0));
+
+    // Already mated
+    let result = search("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", to_depth(3));
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, Score::Mate(0));
+}
+
+#[test]
+fn limits() {
+    let mut depths = Vec::new();
+    let state = parse_fen(STARTING_BOARD_FEN).unwrap();
+    let result = Search::default().search(&state, &[], &to_depth(4), |iteration| depths.push(iteration.depth));
+    assert_eq!(depths, [1, 2, 3, 4]);
+    assert_eq!(result.depth, 4);
+    assert_eq!(result.pv.first(), result.best_move.as_ref());
+
+    let result = search(STARTING_BOARD_FEN, SearchLimits { nodes: Some(5_000), ..Default::default() });
+    assert!(result.nodes <= 5_000);
+    assert!(result.best_move.is_some());
+
+    // Even stopped before it starts, there's always a move to play
+    let stop = Arc::new(AtomicBool::new(true));
+    let result = search(STARTING_BOARD_FEN, SearchLimits { stop: Some(stop), ..Default::default() });
+    assert_eq!(result.depth, 1);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn multiple_lines() {
+    // Two ways to mate, and a lot of ways not to
+    let limits = SearchLimits { depth: Some(3), multi_pv: 3, ..Default::default() };
+    let result = search("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", limits);
+    assert_eq!(result.score, Score::Mate(1));
+    assert_eq!(result.alternatives.len(), 2);
+    assert_eq!(result.alternatives[0].0, Score::Mate(1));
+    assert!(!matches!(result.alternatives[1].0, Score::Mate(_)));
+
+    let mut mates: Vec<String> = vec![result.pv[0].to_string(), result.alternatives[0].1[0].to_string()];
+    mates.sort();
+    assert_eq!(mates, ["a1a8", "e1e8"]);
+}
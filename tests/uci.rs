@@ -0,0 +1,69 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Feeds `input` to the engine binary and hands back everything it printed
+fn session(input: &str) -> Vec<String> {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_engine"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    engine.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = engine.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap().lines().map(str::to_string).collect()
+}
+
+#[test]
+fn handshake() {
+    let output = session("uci\nisready\nquit\n");
+    assert!(output[0].starts_with("id name "));
+    assert!(output.iter().any(|line| line == "option name MultiPV type spin default 1 min 1 max 256"));
+    assert_eq!(output[output.len() - 2..], ["uciok", "readyok"]);
+}
+
+#[test]
+fn plays_moves() {
+    let output = session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\nquit\n");
+    assert!(output.iter().any(|line| line.starts_with("info depth 1 score mate 1 ")));
+    assert_eq!(output.last().unwrap(), "bestmove a1a8");
+
+    // Moves after the position are played before searching, here leaving black to get out of check
+    let output = session("position startpos moves e2e4 f7f6 d2d4 g7g5 d1h5\ngo nodes 1000\nquit\n");
+    assert_eq!(output.last().unwrap(), "bestmove 0000");
+    let output = session("position startpos moves e2e4 e7e5 d1h5\ngo movetime 100\nquit\n");
+    assert!(output.last().unwrap().starts_with("bestmove "));
+
+    // A position with a pawn on the back rank is turned down rather than searched
+    let output = session("position fen P5k1/8/8/8/8/8/8/6K1 w - - 0 1\ngo depth 2\nquit\n");
+    assert_eq!(output[0], "info string invalid FEN: pawn on a8, which is a back rank");
+}
+
+#[test]
+fn multiple_lines() {
+    let output = session(
+        "setoption name MultiPV value 2\nposition fen 6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1\ngo depth 1\nquit\n",
+    );
+    assert!(output[0].starts_with("info depth 1 multipv 1 score mate 1 "));
+    assert!(output[1].starts_with("info depth 1 multipv 2 score mate 1 "));
+}
+
+#[test]
+fn infinite_waits_for_stop() {
+    // Mate in one is found straight away, but the move still only comes after stop
+    let output = session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo infinite\nisready\nstop\nquit\n");
+    let ready = output.iter().position(|line| line == "readyok").unwrap();
+    let best = output.iter().position(|line| line.starts_with("bestmove")).unwrap();
+    assert!(ready < best);
+}
+
+#[test]
+fn new_position_stops_the_search() {
+    // The infinite search is wound up as soon as the next position comes in, not left running
+    let output = session(
+        "position startpos\ngo infinite\nposition fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\nisready\ngo depth 3\nquit\n",
+    );
+    let ready = output.iter().position(|line| line == "readyok").unwrap();
+    let best = output.iter().position(|line| line.starts_with("bestmove")).unwrap();
+    assert!(best < ready);
+    assert_eq!(output.last().unwrap(), "bestmove a1a8");
+}
@@ -0,0 +1,114 @@
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+// The engine binary in xboard mode. Unlike UCI it moves whenever it's its turn, so the tests send
+// a few commands at a time and read back until the answer they're after turns up.
+struct Engine {
+    process: Child,
+    output: Lines<BufReader<ChildStdout>>,
+}
+
+impl Engine {
+    fn start() -> Self {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_engine"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let output = BufReader::new(process.stdout.take().unwrap()).lines();
+        let mut engine = Engine { process, output };
+        engine.send("xboard\nprotover 2\n");
+        engine.read_until("feature ");
+        engine
+    }
+
+    fn send(&mut self, input: &str) {
+        self.process.stdin.as_mut().unwrap().write_all(input.as_bytes()).unwrap();
+    }
+
+    // Everything printed up to and including the first line starting with `prefix`
+    fn read_until(&mut self, prefix: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        for line in self.output.by_ref() {
+            let line = line.unwrap();
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+        panic!("the engine quit without printing {}: {:?}", prefix, lines);
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.process.stdin.as_mut().unwrap().write_all(b"quit\n");
+        let _ = self.process.wait();
+    }
+}
+
+#[test]
+fn feature_negotiation() {
+    let mut engine = Engine::start();
+    engine.send("protover 2\n");
+    let features = engine.read_until("feature ");
+    assert!(features[0].contains("usermove=1"));
+    assert!(features[0].contains("setboard=1"));
+    assert!(features[0].ends_with("done=1"));
+
+    engine.send("accepted usermove\nping 7\n");
+    assert_eq!(engine.read_until("pong"), ["pong 7"]);
+}
+
+#[test]
+fn plays_its_side() {
+    let mut engine = Engine::start();
+    // The engine takes black after new, answering each move
+    engine.send("new\nsd 2\nusermove e2e4\n");
+    let reply = engine.read_until("move ");
+    assert!(reply.last().unwrap().starts_with("move "));
+
+    // go hands it the side to move, and post shows its thinking
+    engine.send("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\npost\ngo\n");
+    let output = engine.read_until("move ");
+    assert!(output.iter().any(|line| line.starts_with("1 100001 ") && line.ends_with("Ra8#")));
+    assert_eq!(output.last().unwrap(), "move a1a8");
+    assert_eq!(engine.read_until("1-0"), ["1-0 {Checkmate, White wins}"]);
+}
+
+#[test]
+fn force_mode_and_take_backs() {
+    let mut engine = Engine::start();
+    // In force mode moves are only recorded, in either notation
+    engine.send("new\nforce\nusermove e2e4\nusermove e5\nusermove e2e5\n");
+    assert_eq!(engine.read_until("Illegal"), ["Illegal move: e2e5"]);
+
+    // Back to the start, where taking back more runs out of moves
+    engine.send("remove\nundo\n");
+    assert_eq!(engine.read_until("Error"), ["Error (no moves to take back): undo"]);
+
+    // Fool's mate, with the engine to move and nothing it can do about it
+    engine.send("usermove f2f3\nusermove e7e5\nusermove g2g4\nsd 3\ngo\n");
+    assert_eq!(engine.read_until("move "), ["move d8h4"]);
+    assert_eq!(engine.read_until("0-1"), ["0-1 {Checkmate, Black wins}"]);
+}
+
+#[test]
+fn time_controls() {
+    let mut engine = Engine::start();
+    engine.send("new\nlevel 40 0:30 0\ntime 100\notim 3000\nusermove d2d4\n");
+    assert!(engine.read_until("move ").last().unwrap().starts_with("move "));
+
+    engine.send("st 0.1\nusermove c2c4\n");
+    assert!(engine.read_until("move ").last().unwrap().starts_with("move "));
+
+    // A new game forgets the last one's time controls. With only a depth limit left it searches all
+    // the way down, where the old game's 10ms clock would have cut it short.
+    engine.send("time 1\nnew\nsd 5\npost\nusermove e2e4\n");
+    let output = engine.read_until("move ");
+    assert!(output.iter().any(|line| line.starts_with("5 ")));
+
+    engine.send("level x\n");
+    assert_eq!(engine.read_until("Error"), ["Error (bad time control): level x"]);
+}
@@ -0,0 +1,69 @@
+use rust_chess_engine::core::{generate_legal_moves, parse_fen, GameState, STARTING_BOARD_FEN};
+
+mod common;
+use common::play;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+// Plays out every line `depth` moves deep, checking the incrementally updated hash against
+// one worked out from scratch after every move and that it's put back after every unmake
+fn check_hashes(state: &mut GameState, depth: u32) {
+    assert_eq!(state.hash, state.compute_hash(), "hash out of sync at {}", state.to_fen());
+    if depth == 0 {
+        return;
+    }
+    for mv in generate_legal_moves(state) {
+        let hash = state.hash;
+        let undo = state.make_move(mv);
+        check_hashes(state, depth - 1);
+        state.unmake_move(mv, undo);
+        assert_eq!(state.hash, hash, "hash not restored after unmaking {}", mv);
+    }
+}
+
+#[test]
+fn incremental_hash_matches_recomputed() {
+    // Between them these cover castling, promotions, en passant and losing castling rights
+    for fen in [STARTING_BOARD_FEN, KIWIPETE, POSITION_3, POSITION_4, POSITION_5].iter() {
+        check_hashes(&mut parse_fen(fen).unwrap(), 3);
+    }
+}
+
+#[test]
+fn transpositions_hash_the_same() {
+    let mut knights_first = parse_fen(STARTING_BOARD_FEN).unwrap();
+    play(&mut knights_first, &mut Vec::new(), &["g1f3", "g8f6", "b1c3", "b8c6"]);
+    let mut other_knights_first = parse_fen(STARTING_BOARD_FEN).unwrap();
+    play(&mut other_knights_first, &mut Vec::new(), &["b1c3", "b8c6", "g1f3", "g8f6"]);
+    assert_eq!(knights_first.hash, other_knights_first.hash);
+
+    // Going out and back again puts everything back the same, so the hash should be too
+    let mut start = parse_fen(STARTING_BOARD_FEN).unwrap();
+    play(&mut start, &mut Vec::new(), &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    assert_eq!(start.hash, parse_fen(STARTING_BOARD_FEN).unwrap().hash);
+}
+
+#[test]
+fn hash_depends_on_side_castling_and_en_passant() {
+    let hash = |fen: &str| parse_fen(fen).unwrap().hash;
+
+    let start = hash(STARTING_BOARD_FEN);
+    assert_ne!(start, hash("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"));
+    assert_ne!(start, hash("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1"));
+    // The clocks aren't part of the position
+    assert_eq!(start, hash("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 12 30"));
+
+    // A black pawn on d4 can take en passant on e3...
+    assert_ne!(
+        hash("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3"),
+        hash("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3")
+    );
+    // ...but with nothing able to take, the en passant square makes no difference
+    assert_eq!(
+        hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+}